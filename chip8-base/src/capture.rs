@@ -0,0 +1,143 @@
+//screenshots and recordings of the display, as PNGs and animated PNGs
+//images are always the size of the high resolution display times the scale, with low resolution frames scaled up to fit,
+//and use an indexed palette of the display colours so they stay small
+
+use crate::{Display, Palette, Resolution};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Saves a display to a PNG file, with each CHIP-8 pixel drawn as a `scale` by `scale` square in high resolution.
+/// Low resolution displays are scaled up to be the same size as high resolution ones.
+pub fn save_png(
+    display: &Display,
+    palette: &Palette,
+    scale: usize,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let mut writer = encoder(&mut out, palette, scale).write_header()?;
+    writer.write_image_data(&indexed(display, scale))?;
+    writer.finish()?;
+    out.flush()
+}
+
+/// A recording of the display, one frame at a time, that can be saved as an animated PNG.
+/// Frames are pushed at 60Hz, and runs of identical frames are stored once so long recordings of mostly still screens stay small.
+#[derive(Debug, Default)]
+pub struct Recording {
+    //each distinct frame, packed, and how many 60Hz frames it was shown for
+    frames: Vec<(Vec<u8>, u16)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a frame to the recording, which is shown for 1/60th of a second
+    pub fn push(&mut self, display: &Display) {
+        let packed = display.pack();
+        match self.frames.last_mut() {
+            Some((last, count)) if *last == packed && *count < u16::MAX => *count += 1,
+            _ => self.frames.push((packed, 1)),
+        }
+    }
+
+    /// How long the recording is, in 60Hz frames
+    pub fn len(&self) -> usize {
+        self.frames.iter().map(|(_, count)| *count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Saves the recording as an animated PNG that loops forever, scaled like [`save_png`].
+    pub fn save(&self, palette: &Palette, scale: usize, path: impl AsRef<Path>) -> io::Result<()> {
+        if self.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Can't save an empty recording",
+            ));
+        }
+        let mut out = BufWriter::new(File::create(path)?);
+        let mut encoder = encoder(&mut out, palette, scale);
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+        let mut writer = encoder.write_header()?;
+        for (packed, count) in &self.frames {
+            writer.set_frame_delay(*count, 60)?;
+            writer.write_image_data(&indexed(&Display::unpack(packed), scale))?;
+        }
+        writer.finish()?;
+        out.flush()
+    }
+}
+
+fn encoder<W: Write>(w: W, palette: &Palette, scale: usize) -> png::Encoder<'static, W> {
+    let (width, height) = (
+        Resolution::High.width() * scale,
+        Resolution::High.height() * scale,
+    );
+    let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.colours.concat());
+    encoder
+}
+
+//one byte per pixel, indexing into the palette
+fn indexed(display: &Display, scale: usize) -> Vec<u8> {
+    let scale = scale * Resolution::High.width() / display.width();
+    let (width, height) = (display.width() * scale, display.height() * scale);
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| display.colour(x / scale, y / scale)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Pixel;
+
+    #[test]
+    fn test_recording() {
+        let mut display = Display::default();
+        let mut recording = Recording::new();
+        recording.push(&display);
+        recording.push(&display);
+        display[0][0] = Pixel::White;
+        recording.push(&display);
+        assert_eq!(recording.frames.len(), 2);
+        assert_eq!(recording.len(), 3);
+    }
+
+    #[test]
+    fn test_save_png() {
+        let mut display = Display::new(Resolution::High);
+        display[3][5] = Pixel::White;
+        let path = std::env::temp_dir().join(format!("chip8-test-{}.png", std::process::id()));
+        save_png(&display, &Palette::default(), 2, &path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (256, 128));
+        assert_eq!(pixels[6 * 256 + 10], 1);
+        assert_eq!(pixels.iter().filter(|p| **p == 1).count(), 4);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_indexed() {
+        let mut display = Display::default();
+        display[1][2] = Pixel::White;
+        let pixels = indexed(&display, 1);
+        assert_eq!(pixels.len(), 128 * 64);
+        //low resolution pixels are 2x2
+        assert_eq!(pixels[2 * 128 + 4..2 * 128 + 6], [1, 1]);
+        assert_eq!(pixels[3 * 128 + 4..3 * 128 + 6], [1, 1]);
+        assert_eq!(pixels.iter().filter(|p| **p == 1).count(), 4);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::Pixel;
+use std::ops::{Index, IndexMut};
+
+/// The resolutions a CHIP-8 display can be in.
+/// The original CHIP-8 only had the low resolution mode, SUPER-CHIP added the high resolution one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Resolution {
+    /// 64x32 pixels
+    #[default]
+    Low,
+    /// 128x64 pixels
+    High,
+}
+
+impl Resolution {
+    /// The width of the display in pixels at this resolution
+    pub const fn width(self) -> usize {
+        match self {
+            Resolution::Low => 64,
+            Resolution::High => 128,
+        }
+    }
+
+    /// The height of the display in pixels at this resolution
+    pub const fn height(self) -> usize {
+        match self {
+            Resolution::Low => 32,
+            Resolution::High => 64,
+        }
+    }
+}
+
+/// The number of bitplanes a display has. XO-CHIP draws to two planes, which combine to give 4 colours.
+pub const PLANES: usize = 2;
+
+/// The Interpreter's representation of the CHIP-8 display.
+/// The display is 64x32 pixels in low resolution or 128x64 in high resolution, each pixel being either Black or White.
+/// XO-CHIP interpreters can also draw to a second plane, see [`colour`][Display::colour].
+///
+/// The display can be indexed by row and then column, so `display[y][x]` is the pixel at `(x, y)` in the first plane.
+/// Each row is only as wide as the current resolution.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Display {
+    resolution: Resolution,
+    planes: [[[Pixel; 128]; 64]; PLANES],
+}
+
+impl Display {
+    /// Creates a new display at the given resolution, with all pixels Black.
+    pub fn new(resolution: Resolution) -> Self {
+        Display {
+            resolution,
+            planes: [[[Pixel::default(); 128]; 64]; PLANES],
+        }
+    }
+
+    /// The current resolution of the display
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// The width of the display in pixels
+    pub fn width(&self) -> usize {
+        self.resolution.width()
+    }
+
+    /// The height of the display in pixels
+    pub fn height(&self) -> usize {
+        self.resolution.height()
+    }
+
+    /// Sets every pixel in every plane of the display to Black
+    pub fn clear(&mut self) {
+        self.planes = [[[Pixel::default(); 128]; 64]; PLANES];
+    }
+
+    /// Sets every pixel in one plane of the display to Black
+    pub fn clear_plane(&mut self, plane: usize) {
+        self.planes[plane] = [[Pixel::default(); 128]; 64];
+    }
+
+    /// An iterator over the rows of the first plane of the display, from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[Pixel]> {
+        (0..self.height()).map(|y| self.row(0, y))
+    }
+
+    /// A row of pixels from the given plane
+    pub fn row(&self, plane: usize, y: usize) -> &[Pixel] {
+        let width = self.width();
+        &self.planes[plane][..self.height()][y][..width]
+    }
+
+    /// A mutable row of pixels from the given plane
+    pub fn row_mut(&mut self, plane: usize, y: usize) -> &mut [Pixel] {
+        let (width, height) = (self.width(), self.height());
+        &mut self.planes[plane][..height][y][..width]
+    }
+
+    /// The colour of the pixel at `(x, y)`, combining all planes.
+    /// Bit 0 is set if the pixel is White in the first plane, and bit 1 if it is White in the second,
+    /// so a display that only uses the first plane has colours 0 and 1.
+    pub fn colour(&self, x: usize, y: usize) -> u8 {
+        (0..PLANES).fold(0, |colour, plane| {
+            colour | u8::from(self.row(plane, y)[x]) << plane
+        })
+    }
+}
+
+//packing displays into bytes, 8 pixels to a byte, so they can be stored compactly
+impl Display {
+    pub(crate) const PACKED_LEN: usize = 1 + PLANES * 64 * 16;
+
+    pub(crate) fn pack(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::PACKED_LEN);
+        bytes.push(self.resolution as u8);
+        for plane in &self.planes {
+            for row in plane {
+                bytes.extend(
+                    row.chunks(8)
+                        .map(|byte| byte.iter().fold(0, |b, px| b << 1 | u8::from(*px))),
+                );
+            }
+        }
+        bytes
+    }
+
+    pub(crate) fn unpack(bytes: &[u8]) -> Display {
+        let resolution = match bytes[0] {
+            0 => Resolution::Low,
+            _ => Resolution::High,
+        };
+        let mut display = Display::new(resolution);
+        let pixels = display.planes.iter_mut().flatten().flatten();
+        let bits = bytes[1..]
+            .iter()
+            .flat_map(|b| (0..8).map(move |i| b >> (7 - i) & 1));
+        for (px, bit) in pixels.zip(bits) {
+            *px = if bit == 1 { Pixel::White } else { Pixel::Black };
+        }
+        display
+    }
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Display::new(Resolution::Low)
+    }
+}
+
+impl From<[[Pixel; 64]; 32]> for Display {
+    fn from(buffer: [[Pixel; 64]; 32]) -> Self {
+        let mut display = Display::new(Resolution::Low);
+        for (row, pixels) in display.planes[0].iter_mut().zip(buffer) {
+            row[..64].copy_from_slice(&pixels);
+        }
+        display
+    }
+}
+
+impl Index<usize> for Display {
+    type Output = [Pixel];
+
+    fn index(&self, y: usize) -> &Self::Output {
+        self.row(0, y)
+    }
+}
+
+impl IndexMut<usize> for Display {
+    fn index_mut(&mut self, y: usize) -> &mut Self::Output {
+        self.row_mut(0, y)
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+/// The ways executing an instruction can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The opcode does not correspond to any instruction the interpreter knows of.
+    InvalidOpcode,
+    /// An instruction tried to access memory outside of the address space, at the given address.
+    MemoryFault(usize),
+    /// A subroutine was called with the stack already full.
+    StackOverflow,
+    /// A subroutine returned with nothing on the stack.
+    StackUnderflow,
+    /// The program counter ran off the end of memory.
+    PcOutOfBounds,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::InvalidOpcode => write!(f, "Invalid instruction encountered"),
+            ErrorKind::MemoryFault(addr) => write!(f, "Memory access out of bounds at {addr:#06X}"),
+            ErrorKind::StackOverflow => write!(f, "Stack overflow"),
+            ErrorKind::StackUnderflow => write!(f, "Stack underflow"),
+            ErrorKind::PcOutOfBounds => write!(f, "Program counter ran off the end of memory"),
+        }
+    }
+}
+
+/// An error returned from [`Interpreter::step`][crate::Interpreter::step], describing what went wrong and where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The address of the instruction that caused the error
+    pub pc: u16,
+    /// The opcode of the instruction that caused the error, if it could be fetched
+    pub opcode: Option<u16>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pc: {:#06X}", self.kind, self.pc)?;
+        if let Some(opcode) = self.opcode {
+            write!(f, ", opcode: {opcode:#06X}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl std::error::Error for Error {}
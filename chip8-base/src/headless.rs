@@ -0,0 +1,146 @@
+use crate::{Display, Error, Interpreter, Keys, Movie};
+use std::collections::VecDeque;
+
+type TimerTick<I> = Box<dyn FnMut(&mut I)>;
+
+/// Runs an [`Interpreter`][Interpreter] without a window or any audio, for testing and automation.
+/// Unlike [`run`][crate::run], this doesn't keep to real time, and instead runs as fast as it can for a given number of cycles or frames.
+///
+/// A frame is 1/60th of a second, so the number of cycles in a frame is worked out from [`speed`][Interpreter::speed],
+/// unless it's set with [`instructions_per_frame`][Headless::instructions_per_frame].
+/// At the end of each frame [`timer_tick`][Interpreter::timer_tick] is called and the buzzer is sampled.
+pub struct Headless<I> {
+    interpreter: I,
+    keys: Keys,
+    script: VecDeque<(u64, Keys)>,
+    display: Display,
+    buzzer: Vec<bool>,
+    cycles: u64,
+    timer: Option<TimerTick<I>>,
+    instructions_per_frame: Option<u64>,
+}
+
+impl<I: Interpreter> Headless<I> {
+    /// Creates a new headless runner, with no keys pressed.
+    pub fn new(interpreter: I) -> Self {
+        Headless {
+            interpreter,
+            keys: [false; 16],
+            script: VecDeque::new(),
+            display: Display::default(),
+            buzzer: Vec::new(),
+            cycles: 0,
+            timer: None,
+            instructions_per_frame: None,
+        }
+    }
+
+    /// Scripts key input, as a list of cycle numbers and the state the keys should be in from that cycle onwards.
+    pub fn script(mut self, events: impl IntoIterator<Item = (u64, Keys)>) -> Self {
+        self.script.extend(events);
+        self.script
+            .make_contiguous()
+            .sort_by_key(|(cycle, _)| *cycle);
+        self
+    }
+
+    /// Sets up a replay of a [`Movie`], loading its starting state into the interpreter and scripting its input.
+    /// The movie's instructions per frame are used too, if it has them.
+    /// This fails if the movie has a save state that the interpreter can't load.
+    pub fn movie(mut self, movie: &Movie) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(state) = &movie.state {
+            self.interpreter.load_state(state)?;
+        }
+        if let Some(n) = movie.instructions_per_frame {
+            self = self.instructions_per_frame(n);
+        }
+        Ok(self.script(movie.events.iter().copied()))
+    }
+
+    /// Sets how many cycles are run in each frame, instead of working it out from [`speed`][Interpreter::speed].
+    pub fn instructions_per_frame(mut self, n: u32) -> Self {
+        self.instructions_per_frame = Some(n.max(1).into());
+        self
+    }
+
+    /// Sets a function to be called on the interpreter at the end of every frame, after [`timer_tick`][Interpreter::timer_tick].
+    pub fn with_timer(mut self, tick: impl FnMut(&mut I) + 'static) -> Self {
+        self.timer = Some(Box::new(tick));
+        self
+    }
+
+    /// Presses a key down, holding it until it is released
+    pub fn press(&mut self, key: usize) {
+        self.keys[key] = true;
+    }
+
+    /// Releases a held key
+    pub fn release(&mut self, key: usize) {
+        self.keys[key] = false;
+    }
+
+    /// Runs the interpreter for `n` cycles, stopping early if it returns an error.
+    pub fn run_cycles(&mut self, n: u64) -> Result<(), Error> {
+        for _ in 0..n {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Runs the interpreter for `n` frames, stopping early if it returns an error.
+    pub fn run_frames(&mut self, n: u64) -> Result<(), Error> {
+        self.run_cycles(n * self.cycles_per_frame())
+    }
+
+    /// The number of cycles the interpreter executes in each frame, at its current speed.
+    pub fn cycles_per_frame(&self) -> u64 {
+        self.instructions_per_frame
+            .unwrap_or_else(|| crate::cycles_per_frame(self.interpreter.speed()).into())
+    }
+
+    /// The most recent display returned by the interpreter
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    /// Whether the buzzer was active at the end of each frame run so far
+    pub fn buzzer_history(&self) -> &[bool] {
+        &self.buzzer
+    }
+
+    /// The number of cycles run so far
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// The interpreter being run
+    pub fn interpreter(&self) -> &I {
+        &self.interpreter
+    }
+
+    /// Consumes the runner, returning the interpreter
+    pub fn into_inner(self) -> I {
+        self.interpreter
+    }
+
+    fn step(&mut self) -> Result<(), Error> {
+        while let Some((_, keys)) = self.script.front().filter(|(c, _)| *c <= self.cycles) {
+            self.keys = *keys;
+            self.script.pop_front();
+        }
+
+        if let Some(update) = self.interpreter.step(&self.keys)? {
+            self.display = update;
+        }
+        self.cycles += 1;
+
+        if self.cycles.is_multiple_of(self.cycles_per_frame()) {
+            self.interpreter.timer_tick();
+            if let Some(tick) = &mut self.timer {
+                tick(&mut self.interpreter);
+            }
+            self.buzzer.push(self.interpreter.buzzer_active());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{save_png, Display, Palette, Recording};
+use anyhow::Context;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+
+//screenshots and recordings taken with hotkeys, saved to numbered files in the working directory
+pub struct Capture {
+    palette: Palette,
+    scale: usize,
+    recording: Option<Recording>,
+    //recordings being saved in the background, which have to finish before exiting or the files are cut short
+    saving: Vec<JoinHandle<()>>,
+}
+
+impl Capture {
+    pub fn new(palette: Palette, scale: usize) -> Self {
+        Capture {
+            palette,
+            scale,
+            recording: None,
+            saving: Vec::new(),
+        }
+    }
+
+    pub fn screenshot(&self, display: &Display) -> anyhow::Result<()> {
+        let path = unused_path("screenshot");
+        save_png(display, &self.palette, self.scale, &path)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        log::info!("Saved screenshot to {}", path.display());
+        Ok(())
+    }
+
+    //called at 60Hz with the current display
+    pub fn frame(&mut self, display: &Display) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(display);
+        }
+    }
+
+    //start recording, or stop and save the recording in the background
+    pub fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(recording) => {
+                let (palette, scale) = (self.palette, self.scale);
+                self.saving.retain(|handle| !handle.is_finished());
+                self.saving.push(std::thread::spawn(move || {
+                    save(&recording, &palette, scale)
+                }));
+            }
+            None => {
+                log::info!("Started recording");
+                self.recording = Some(Recording::new());
+            }
+        }
+    }
+
+    //save anything still being recorded, and wait for recordings being saved, before exiting
+    pub fn finish(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            save(&recording, &self.palette, self.scale);
+        }
+        for handle in self.saving.drain(..) {
+            if handle.join().is_err() {
+                log::error!("Saving a recording panicked");
+            }
+        }
+    }
+}
+
+fn save(recording: &Recording, palette: &Palette, scale: usize) {
+    let path = unused_path("recording");
+    match recording.save(palette, scale, &path) {
+        Ok(()) => log::info!(
+            "Saved {:.1}s recording to {}",
+            recording.len() as f64 / 60.0,
+            path.display()
+        ),
+        Err(e) => log::error!("Could not write {}: {e}", path.display()),
+    }
+}
+
+//the first of chip8-{kind}-0.png, chip8-{kind}-1.png... that doesn't exist yet
+fn unused_path(kind: &str) -> PathBuf {
+    (0..)
+        .map(|n| PathBuf::from(format!("chip8-{kind}-{n}.png")))
+        .find(|path| !path.exists())
+        .unwrap()
+}
@@ -1,43 +1,52 @@
 use std::io::Write;
 
+use super::RunOptions;
+use crate::{Display, Palette, Resolution};
 use anyhow::Context;
-use pixels::{Pixels, SurfaceTexture};
+use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
 use winit::dpi::LogicalSize;
 use winit::event_loop::EventLoop;
-use winit::window::{Window, WindowBuilder};
+use winit::window::{Fullscreen, Window, WindowBuilder};
 
-pub fn init() -> anyhow::Result<(EventLoop<()>, Window, Pixels)> {
-    //new event loop and input helper
-    let event_loop = EventLoop::new();
-    let size = (640, 320);
+pub fn init(event_loop: &EventLoop<()>, options: &RunOptions) -> anyhow::Result<(Window, Pixels)> {
+    let (width, height) = (
+        Resolution::High.width() as u32,
+        Resolution::High.height() as u32,
+    );
     //initialise our winit window
     let window: Window = {
-        let size = LogicalSize::new(size.0 as f64, size.1 as f64);
+        let scale = options.window_scale;
         WindowBuilder::new()
-            .with_title("CHIP-8")
-            .with_inner_size(size)
-            .with_min_inner_size(size)
-            .build(&event_loop)?
+            .with_title(&options.title)
+            .with_inner_size(LogicalSize::new(width * scale, height * scale))
+            .with_min_inner_size(LogicalSize::new(width, height))
+            .with_fullscreen(options.fullscreen.then_some(Fullscreen::Borderless(None)))
+            .build(event_loop)?
     };
 
     //initialise our Pixels
     let pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(64, 32, surface_texture).context("Could not create pixels display surface")?
+        //the buffer is always the size of the high resolution display, and low resolution frames are scaled up to fit
+        PixelsBuilder::new(width, height, surface_texture)
+            .enable_vsync(options.vsync)
+            .build()
+            .context("Could not create pixels display surface")?
     };
-    Ok((event_loop, window, pixels))
+    Ok((window, pixels))
 }
 
-pub fn update(pixels: &mut Pixels, buffer: &[[crate::Pixel; 64]; 32]) -> anyhow::Result<()> {
+pub fn update(pixels: &mut Pixels, buffer: &Display, palette: &Palette) -> anyhow::Result<()> {
+    let scale = Resolution::High.width() / buffer.width();
     let mut old_buf = pixels.get_frame();
-    for px in buffer.concat() {
-        old_buf
-            .write_all(match px {
-                crate::Pixel::Black => &[0_u8, 0_u8, 0_u8, 255_u8],
-                crate::Pixel::White => &[255_u8, 255_u8, 255_u8, 255_u8],
-            })
-            .context("Error when writing data to internal pixels buffer")?
+    for y in 0..Resolution::High.height() {
+        for x in 0..Resolution::High.width() {
+            let colour = buffer.colour(x / scale, y / scale);
+            old_buf
+                .write_all(&palette.rgba(colour))
+                .context("Error when writing data to internal pixels buffer")?
+        }
     }
     Ok(())
 }
@@ -1,125 +1,178 @@
+mod capture;
 mod display;
 mod input;
+mod options;
+mod overlay;
+mod rewind;
 mod sound;
+mod speed;
+mod state;
+mod terminal;
+mod vm;
 
-use crate::{Interpreter, Pixel};
-use anyhow::Context;
-use crossbeam::atomic::AtomicCell;
-use crossbeam::sync::WaitGroup;
-use std::error::Error;
+pub use options::{Frontend, RunOptions};
+
+use crate::Interpreter;
+use anyhow::{anyhow, Context};
+use std::cell::RefCell;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
-use std::thread;
-use std::time::Instant;
+use vm::Vm;
 use winit::event::{Event, VirtualKeyCode};
-use winit::event_loop::ControlFlow;
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::platform::run_return::EventLoopExtRunReturn;
 use winit_input_helper::WinitInputHelper;
 
-/// Starts the interpreter, blocking the current thread and running until killed.
+/// Starts the interpreter, opening a window and blocking the current thread until it's closed.
 /// Windowing, graphics, sound, and timing are all handled within this method.
-pub fn run<I>(mut interpreter: I) -> !
+///
+/// # Hotkeys
+/// - `Escape` quits
+/// - `F5` saves the interpreter's state to the current slot, and `F9` loads it back
+/// - `F6` and `F7` select the previous and next save state slot
+/// - Holding `Backspace` rewinds, going back one frame at a time
+/// - `F2` pauses and resumes, and `F3` runs one frame while paused
+/// - `PageUp` and `PageDown` double and halve the speed, and holding `Tab` fast forwards at 4x.
+///   The window title shows how many instructions a second are running
+/// - `F1` shows or hides which keys on the keyboard press each CHIP-8 key, see [`Keymap`][crate::Keymap]
+/// - `F12` saves a screenshot, and `F10` starts or stops recording the display as an animated PNG.
+///   These are saved to numbered files in the working directory, like `chip8-screenshot-0.png`
+///
+/// Save states and rewinding are only available if the interpreter implements [`save_state`][Interpreter::save_state] and [`load_state`][Interpreter::load_state].
+/// Rewinding and loading states are also turned off while recording or replaying a [`Movie`][crate::Movie].
+///
+/// # Errors
+/// Returns `Ok` once the window is closed, or the interpreter asks to [quit][Interpreter::quit_requested]. If the interpreter stops because [`step`][Interpreter::step] returned an error,
+/// the window is closed and the error is returned, which can be recovered with `downcast_ref::<chip8_base::Error>()`.
+/// Errors setting up the window or a movie are returned too.
+///
+/// Like any windowed program, this has to be called from the main thread.
+pub fn run<I>(interpreter: I) -> anyhow::Result<()>
+where
+    I: Interpreter + Send + 'static,
+{
+    run_with(interpreter, RunOptions::default())
+}
+
+thread_local! {
+    //winit only allows one event loop per program, so it's kept to reuse if run is called again
+    static EVENT_LOOP: RefCell<Option<EventLoop<()>>> = const { RefCell::new(None) };
+}
+
+/// Starts the interpreter like [`run`][run], but with the given options.
+/// [`RunOptions::frontend`] can draw it in the terminal instead of a window, which works over SSH.
+pub fn run_with<I>(interpreter: I, options: RunOptions) -> anyhow::Result<()>
+where
+    I: Interpreter + Send + 'static,
+{
+    if options.frontend == Frontend::Terminal {
+        return terminal::run(interpreter, options);
+    }
+    let mut event_loop = EVENT_LOOP
+        .with(|e| e.borrow_mut().take())
+        .unwrap_or_default();
+    let result = run_on(&mut event_loop, interpreter, options);
+    EVENT_LOOP.with(|e| *e.borrow_mut() = Some(event_loop));
+    result
+}
+
+fn run_on<I>(
+    event_loop: &mut EventLoop<()>,
+    interpreter: I,
+    options: RunOptions,
+) -> anyhow::Result<()>
 where
     I: Interpreter + Send + 'static,
 {
     //init display subsystem
     log::info!("Initalising display components...");
-    let (event_loop, window, mut pixels) = display::init()
-        .context("Could not initialise display subsystem.")
-        .unwrap(); //failure to init display is fatal, so panic.
+    let (window, mut pixels) =
+        display::init(event_loop, &options).context("Could not initialise display subsystem.")?;
 
     //init input subsystem
     log::info!("Initalising input components...");
     let mut input = WinitInputHelper::new();
 
-    //include a flag so we know if the current frame has been drawn, to avoid drawing it twice
-    let frame_buffer = Arc::new(AtomicCell::new(([[Pixel::default(); 64]; 32], false)));
-    let input_buffer = Arc::new(AtomicCell::new([false; 16]));
-
-    //used so CPU doesnt start until display is ready
-    //cant start CPU after display because display has to be on the main thread and blocks it
-    let wg = WaitGroup::new();
-
-    let handle = thread::Builder::new().name("VM Executor".to_string()).spawn({
-        //make copies of what we need
-        let wg = wg.clone();
-        let frame_buffer = frame_buffer.clone();
-        let input_buffer = input_buffer.clone();
-
-        //start thread
-        move || {
-            //init the audio on the thread because cpal::stream:  !send
-            log::info!("Initalising audio components...");
-            let buzzer = sound::Buzzer::init()
-                .map_err(|e| {
-                    log::error!("Failure in initalising audio: {e:?}. Continuing with no sound.")
-                })
-                .ok();
-
-            log::info!("Starting CPU...");
-            wg.wait(); //wait until event loop ready
-            loop {
-                let t0 = Instant::now();
-                //step the cpu, read input buffer, write to framebuffer
-                if let Some(update) = interpreter.step(&input_buffer.load()) {
-                    frame_buffer.store((update, false));
-                }
-
-                //handle sound
-                if let Some(buzzer) = &buzzer {
-                    buzzer.switch.store(interpreter.buzzer_active(), Ordering::Relaxed);
-                }
-
-                //sleep to make time steps uniform
-                if let Some(sleepy_time) = interpreter.speed().checked_sub(Instant::now() - t0) {
-                    thread::sleep(sleepy_time);
-                    log::debug!(
-                        "Took {:?} to execute instruction",
-                        interpreter.speed() - sleepy_time
-                    )
-                } else {
-                    log::warn!("CPU clock is running slow, your interpreter is taking too long to execute instructions.")
-                }
-            }
-        }
-    }).context("Could not start VM execution thread").unwrap();
+    let mut vm = Vm::start(interpreter, &options)?;
 
     //event loop starts here
-    wg.wait(); //start other thread
     log::info!("Starting input & display event loop...");
 
-    event_loop.run(move |event, _, control_flow| {
-        //if cpu thread has exited (due to panic), exit
-        if handle.is_finished() {
-            log::error!("VM thread has exited, shutting down...");
+    let mut result = Ok(());
+    let mut show_keymap = false;
+    let mut title = options.title.clone();
+    event_loop.run_return(|event, _, control_flow| {
+        //if cpu thread has exited (due to an error, panic or quitting), close the window and return why
+        if let Some(finished) = vm.finished() {
+            result = finished;
+            vm.exit(&options);
             *control_flow = ControlFlow::Exit;
             return;
         }
 
-        let new_frame = frame_buffer.load();
+        let new_frame = vm.frame_buffer.load();
 
         //only redraw if there was an update
         if !new_frame.1 {
-            display::update(&mut pixels, &new_frame.0)
-                .context("Failed to update display")
-                .unwrap(); //panic if failed to update display for whatever reason
+            if let Err(e) = display::update(&mut pixels, &new_frame.0, &options.palette) {
+                result = Err(e.context("Failed to update display"));
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+        }
+        if show_keymap {
+            overlay::draw(
+                pixels.get_frame(),
+                &options.keymap,
+                &vm.input_buffer.load(),
+                &options.palette,
+            );
         }
 
         //if the OS requested a redraw of the window
         if let Event::RedrawRequested(_) = event {
             if let Err(e) = pixels.render() {
-                panic!("Pixels rendering failure, caused by: {:?}", e.source());
+                result = Err(anyhow!(e).context("Pixels rendering failure"));
+                *control_flow = ControlFlow::Exit;
+                return;
             }
         }
 
         // Handle input events
         if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+            // Close events, which the close handler can veto
+            let close_requested =
+                input.quit() || (options.escape_quits && input.key_pressed(VirtualKeyCode::Escape));
+            if close_requested
+                && options
+                    .on_close
+                    .as_ref()
+                    .is_none_or(|handler| (handler.0)())
+            {
+                vm.exit(&options);
                 *control_flow = ControlFlow::Exit;
                 return;
             }
+            if input.key_pressed(VirtualKeyCode::F1) {
+                show_keymap = !show_keymap;
+            }
+            for key in vm::HOTKEYS {
+                if input.key_pressed(key) {
+                    vm.hotkey(key);
+                }
+            }
+            vm.rewinding
+                .store(input.key_held(VirtualKeyCode::Back), Ordering::Relaxed);
+            vm.speed
+                .set_fast_forward(input.key_held(VirtualKeyCode::Tab));
+            let new_title = vm.title(&options.title);
+            if new_title != title {
+                window.set_title(&new_title);
+                title = new_title;
+            }
+
             //handle keyboard input to emulator
-            input_buffer.swap(input::key_state(&input));
+            vm.input_buffer
+                .swap(input::key_state(&input, &options.keymap));
 
             // Resize the window
             if let Some(size) = input.window_resized() {
@@ -128,4 +181,8 @@ where
         }
         window.request_redraw();
     });
+
+    //the window closed first, so stop the interpreter and wait for it to finish
+    vm.stop();
+    result
 }
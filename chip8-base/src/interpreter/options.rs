@@ -0,0 +1,236 @@
+use crate::{Keymap, Movie, Palette};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Options for how [`run_with`][crate::run_with] runs an interpreter.
+/// The defaults are what [`run`][crate::run] uses.
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+    pub(crate) rewind_depth: usize,
+    pub(crate) save_states: String,
+    pub(crate) record: Option<PathBuf>,
+    pub(crate) replay: Option<Movie>,
+    pub(crate) capture_scale: usize,
+    pub(crate) exit_screenshot: Option<PathBuf>,
+    pub(crate) palette: Palette,
+    pub(crate) keymap: Keymap,
+    pub(crate) window_scale: u32,
+    pub(crate) title: String,
+    pub(crate) fullscreen: bool,
+    pub(crate) vsync: bool,
+    pub(crate) audio: bool,
+    pub(crate) buzzer_frequency: f32,
+    pub(crate) escape_quits: bool,
+    pub(crate) on_close: Option<CloseHandler>,
+    pub(crate) instructions_per_frame: Option<u32>,
+    pub(crate) frame_batching: bool,
+    pub(crate) uncapped: bool,
+    pub(crate) frontend: Frontend,
+}
+
+/// Where [`run_with`][crate::run_with] shows the display and takes input from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Frontend {
+    /// A window, drawn with the GPU
+    #[default]
+    Window,
+    /// The terminal, drawn with Unicode block or braille characters and a colour for each.
+    /// Most terminals only report keys being pressed and not released, so keys are held for a moment after each press,
+    /// and staying held relies on key repeat. Terminals that support the kitty keyboard protocol report releases too.
+    /// Logging should be sent somewhere other than the terminal, or it will draw over the display.
+    Terminal,
+}
+
+/// Parses `window`, or `tui` or `terminal`
+impl FromStr for Frontend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "window" => Ok(Frontend::Window),
+            "tui" | "terminal" => Ok(Frontend::Terminal),
+            _ => Err(format!("Frontends are window or tui, not `{s}`")),
+        }
+    }
+}
+
+//closures aren't Debug, so this stands in for one
+#[derive(Clone)]
+pub(crate) struct CloseHandler(pub Arc<dyn Fn() -> bool + Send + Sync>);
+
+impl fmt::Debug for CloseHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CloseHandler")
+    }
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            //10 seconds
+            rewind_depth: 600,
+            save_states: "chip8".to_string(),
+            record: None,
+            replay: None,
+            capture_scale: 4,
+            exit_screenshot: None,
+            palette: Palette::default(),
+            keymap: Keymap::default(),
+            //640x320
+            window_scale: 5,
+            title: "CHIP-8".to_string(),
+            fullscreen: false,
+            vsync: true,
+            audio: true,
+            buzzer_frequency: 440.0,
+            escape_quits: true,
+            on_close: None,
+            instructions_per_frame: None,
+            frame_batching: false,
+            uncapped: false,
+            frontend: Frontend::Window,
+        }
+    }
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the starting size of the window, in screen pixels per high resolution CHIP-8 pixel.
+    /// The default is 5, for a 640x320 window. The window can still be resized.
+    pub fn window_scale(mut self, scale: u32) -> Self {
+        self.window_scale = scale.max(1);
+        self
+    }
+
+    /// Sets the window title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Starts in borderless fullscreen on the current monitor, instead of in a window
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// Sets whether drawing waits for the monitor's vertical sync, which is on by default.
+    /// Turning it off can reduce input latency at the cost of tearing.
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    /// Turns sound on or off. It's on by default, and carries on without sound if no audio device can be opened.
+    pub fn audio(mut self, audio: bool) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Sets the pitch in Hz of the tone played while the buzzer is active, 440 by default.
+    /// XO-CHIP audio patterns play at their own rate instead.
+    pub fn buzzer_frequency(mut self, hz: f32) -> Self {
+        self.buzzer_frequency = hz;
+        self
+    }
+
+    /// Sets whether pressing `Escape` closes the window, which it does by default
+    pub fn escape_quits(mut self, escape_quits: bool) -> Self {
+        self.escape_quits = escape_quits;
+        self
+    }
+
+    /// Sets a function to call when the window is asked to close, either with the close button or `Escape`.
+    /// The window only closes if it returns `true`, so returning `false` keeps the interpreter running.
+    pub fn on_close(mut self, handler: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        self.on_close = Some(CloseHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Sets how many instructions run in each 60Hz frame, instead of working it out from [`speed`][crate::Interpreter::speed].
+    /// The timers always tick 60 times a second, so this only changes how fast the program itself runs.
+    pub fn instructions_per_frame(mut self, n: u32) -> Self {
+        self.instructions_per_frame = Some(n.max(1));
+        self
+    }
+
+    /// Runs a whole frame's worth of instructions at once, then presents the display and sleeps until the next frame,
+    /// instead of sleeping after every instruction. This is smoother and uses less CPU, as most emulators do.
+    /// A frame ends early if the interpreter is [waiting for the next one][crate::Interpreter::waiting_for_vblank].
+    pub fn frame_batching(mut self, batch: bool) -> Self {
+        self.frame_batching = batch;
+        self
+    }
+
+    /// Runs instructions as fast as possible instead of sleeping between them, to measure how fast the interpreter is.
+    /// The timers tick every frame's worth of instructions rather than in real time, so programs behave the same, only faster.
+    /// The title shows how many instructions a second are running, which is also logged every second.
+    pub fn uncapped(mut self, uncapped: bool) -> Self {
+        self.uncapped = uncapped;
+        self
+    }
+
+    /// Sets where the display is shown and input comes from, a window by default.
+    /// The window only options are ignored in the terminal, and the keymap overlay isn't available there.
+    pub fn frontend(mut self, frontend: Frontend) -> Self {
+        self.frontend = frontend;
+        self
+    }
+
+    /// Sets how many frames of history are kept for rewinding. Setting this to 0 disables rewinding.
+    pub fn rewind_depth(mut self, frames: usize) -> Self {
+        self.rewind_depth = frames;
+        self
+    }
+
+    /// Sets what save state files are called, which is `chip8` by default for files like `chip8-slot-0.state`.
+    /// Give each program its own name, or they share slots and load each other's states.
+    pub fn save_states(mut self, name: impl Into<String>) -> Self {
+        self.save_states = name.into();
+        self
+    }
+
+    /// Records the input to a [`Movie`] file as the interpreter runs, starting from its current save state.
+    /// Rewinding and loading save states are disabled while recording, as they would make the movie impossible to replay.
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record = Some(path.into());
+        self
+    }
+
+    /// Sets the colours the display is drawn in, which screenshots and recordings are saved in too
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Sets which keys on the keyboard press each CHIP-8 key. `F1` shows the keymap over the display while running.
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Sets how many image pixels each high resolution CHIP-8 pixel takes up in screenshots and recordings.
+    /// Low resolution pixels are twice the size, so images are always the same size.
+    pub fn capture_scale(mut self, scale: usize) -> Self {
+        self.capture_scale = scale.max(1);
+        self
+    }
+
+    /// Saves a screenshot of the display to a PNG file when the window is closed or the interpreter stops
+    pub fn exit_screenshot(mut self, path: impl Into<PathBuf>) -> Self {
+        self.exit_screenshot = Some(path.into());
+        self
+    }
+
+    /// Replays a [`Movie`], loading its starting state and feeding its input to the interpreter in place of the keyboard.
+    /// Rewinding and loading save states are disabled while replaying.
+    pub fn replay(mut self, movie: Movie) -> Self {
+        self.replay = Some(movie);
+        self
+    }
+}
@@ -0,0 +1,134 @@
+//an overlay drawn over the display showing which keyboard keys press each CHIP-8 key
+//the keypad is drawn as a 4x4 grid filling the high resolution buffer, so each cell is 32x16 pixels
+//with the CHIP-8 key and the first key bound to it on the top line, and the second key underneath
+
+use crate::keymap::KEYPAD;
+use crate::{Keymap, Keys, Palette, Resolution};
+
+const CELL_WIDTH: usize = 32;
+const CELL_HEIGHT: usize = 16;
+//characters are 3x5, with a column of space after each
+const CHAR_WIDTH: usize = 4;
+
+pub fn draw(frame: &mut [u8], keymap: &Keymap, keys: &Keys, palette: &Palette) {
+    for (n, chip8_key) in KEYPAD.iter().enumerate() {
+        let (x, y) = ((n % 4) * CELL_WIDTH, (n / 4) * CELL_HEIGHT);
+        //pressed keys are drawn inverted
+        let (background, foreground) = match keys[*chip8_key as usize] {
+            true => (1, 0),
+            false => (0, 1),
+        };
+        let mut cell = Cell {
+            frame,
+            palette,
+            x,
+            y,
+        };
+        cell.fill(background);
+        let mut labels = keymap.labels(*chip8_key);
+        cell.text(2, 2, &format!("{chip8_key:X}"), foreground);
+        if let Some(label) = labels.next() {
+            cell.text(8, 2, label, foreground);
+        }
+        if let Some(label) = labels.next() {
+            cell.text(8, 9, label, foreground);
+        }
+    }
+}
+
+//drawing within one cell of the grid, clipped to the cell
+struct Cell<'a> {
+    frame: &'a mut [u8],
+    palette: &'a Palette,
+    x: usize,
+    y: usize,
+}
+
+impl Cell<'_> {
+    fn set(&mut self, x: usize, y: usize, colour: u8) {
+        if x >= CELL_WIDTH || y >= CELL_HEIGHT {
+            return;
+        }
+        let i = ((self.y + y) * Resolution::High.width() + self.x + x) * 4;
+        self.frame[i..i + 4].copy_from_slice(&self.palette.rgba(colour));
+    }
+
+    //the background, with the edges in the second plane's colour so the cells stand apart
+    fn fill(&mut self, colour: u8) {
+        for y in 0..CELL_HEIGHT {
+            for x in 0..CELL_WIDTH {
+                let edge = x == CELL_WIDTH - 1 || y == CELL_HEIGHT - 1;
+                self.set(x, y, if edge { 2 } else { colour });
+            }
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, colour: u8) {
+        for (n, c) in text.chars().enumerate() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        self.set(x + n * CHAR_WIDTH + col, y + row, colour);
+                    }
+                }
+            }
+        }
+    }
+}
+
+//a 3x5 font covering the key labels, a row per byte with the leftmost pixel as bit 2
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '*' => [0b101, 0b010, 0b111, 0b010, 0b101],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '\\' => [0b100, 0b100, 0b010, 0b001, 0b001],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '`' => [0b100, 0b010, 0b000, 0b000, 0b000],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        ' ' => [0; 5],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{Display, Interpreter};
+use std::collections::VecDeque;
+
+//a history of the interpreter's state, one entry per frame, so it can be stepped backwards in time
+//only the newest state is stored in full, older ones are stored as the bytes that changed from the next newest state
+//most of memory doesn't change from frame to frame, so this keeps the size of the buffer down a lot
+pub struct Rewind {
+    depth: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Delta>,
+}
+
+impl Rewind {
+    pub fn new(depth: usize) -> Self {
+        Rewind {
+            depth,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    //record the current state of the interpreter, along with what's on the display
+    pub fn push<I: Interpreter>(&mut self, interpreter: &I, display: &Display) {
+        if self.depth == 0 {
+            return;
+        }
+        let Some(mut state) = interpreter.save_state() else {
+            return;
+        };
+        state.extend(display.pack());
+
+        if let Some(previous) = self.latest.replace(state) {
+            self.deltas
+                .push_back(Delta::new(self.latest.as_ref().unwrap(), &previous));
+            if self.deltas.len() > self.depth {
+                self.deltas.pop_front();
+            }
+        }
+    }
+
+    //go back one frame, restoring the interpreter's state and returning the display as it was
+    pub fn pop<I: Interpreter>(&mut self, interpreter: &mut I) -> Option<Display> {
+        let delta = self.deltas.pop_back()?;
+        let state = delta.apply(self.latest.as_ref()?);
+
+        let (vm_state, display) = state.split_at(state.len() - Display::PACKED_LEN);
+        if let Err(e) = interpreter.load_state(vm_state) {
+            log::error!("Could not rewind: {e}");
+            return None;
+        }
+        let display = Display::unpack(display);
+        self.latest = Some(state);
+        Some(display)
+    }
+}
+
+//the changes needed to turn one state into another, as runs of bytes and the offsets they start at
+struct Delta {
+    len: usize,
+    runs: Vec<(usize, Vec<u8>)>,
+}
+
+impl Delta {
+    //the delta from `from` to `to`
+    fn new(from: &[u8], to: &[u8]) -> Self {
+        let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+        for (i, byte) in to.iter().enumerate() {
+            if from.get(i) == Some(byte) {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((start, run)) if *start + run.len() == i => run.push(*byte),
+                _ => runs.push((i, vec![*byte])),
+            }
+        }
+        Delta {
+            len: to.len(),
+            runs,
+        }
+    }
+
+    fn apply(&self, from: &[u8]) -> Vec<u8> {
+        let mut to = from[..self.len.min(from.len())].to_vec();
+        to.resize(self.len, 0);
+        for (start, run) in &self.runs {
+            to[*start..(start + run.len())].copy_from_slice(run);
+        }
+        to
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Delta;
+
+    #[test]
+    fn test_delta() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let b = [1, 9, 9, 4, 5, 7, 8];
+        let delta = Delta::new(&a, &b);
+        assert_eq!(delta.runs, vec![(1, vec![9, 9]), (5, vec![7, 8])]);
+        assert_eq!(delta.apply(&a), b);
+        assert_eq!(Delta::new(&b, &a).apply(&b), a);
+    }
+}
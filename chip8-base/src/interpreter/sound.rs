@@ -1,8 +1,10 @@
 //based on https://github.com/RustAudio/cpal/blob/1ac8f1549f41001acd0acef2be9214ab72e61d11/examples/beep.rs
 
+use crate::AudioPattern;
 use anyhow::{anyhow, Context};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Sample, SampleFormat};
+use crossbeam::atomic::AtomicCell;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -10,11 +12,13 @@ use std::sync::Arc;
 //we have to store the stream to keep the thread alive
 pub struct Buzzer {
     pub(super) switch: Arc<AtomicBool>,
+    pub(super) pattern: Arc<AtomicCell<Option<AudioPattern>>>,
     _stream: cpal::Stream,
 }
 
 impl Buzzer {
-    pub fn init() -> anyhow::Result<Self> {
+    //frequency is the pitch of the tone played when there's no audio pattern
+    pub fn init(frequency: f32) -> anyhow::Result<Self> {
         //default audio host and output device
         let host = cpal::default_host();
         let device = host
@@ -28,18 +32,21 @@ impl Buzzer {
             .context("Could not get default audio device")?;
 
         let switch = Arc::<AtomicBool>::default();
+        let pattern = Arc::<AtomicCell<Option<AudioPattern>>>::default();
 
         //run audio stream
         //starts it's own background thread
+        let (sw, pat) = (switch.clone(), pattern.clone());
         let stream = match config.sample_format() {
-            SampleFormat::F32 => start::<f32>(&device, &config.into(), switch.clone()),
-            SampleFormat::I16 => start::<i16>(&device, &config.into(), switch.clone()),
-            SampleFormat::U16 => start::<u16>(&device, &config.into(), switch.clone()),
+            SampleFormat::F32 => start::<f32>(&device, &config.into(), frequency, sw, pat),
+            SampleFormat::I16 => start::<i16>(&device, &config.into(), frequency, sw, pat),
+            SampleFormat::U16 => start::<u16>(&device, &config.into(), frequency, sw, pat),
         }
         .context("Could not start audio stream")?;
 
         Ok(Buzzer {
             switch,
+            pattern,
             _stream: stream,
         })
     }
@@ -48,7 +55,9 @@ impl Buzzer {
 fn start<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
+    frequency: f32,
     volume_switch: Arc<AtomicBool>,
+    pattern: Arc<AtomicCell<Option<AudioPattern>>>,
 ) -> anyhow::Result<cpal::Stream>
 where
     T: cpal::Sample,
@@ -60,7 +69,19 @@ where
     let mut sample_clock = 0f32;
     let mut next_sample = move || {
         sample_clock = (sample_clock + 1.0) % sample_rate;
-        (sample_clock * 440.0 * 2.0 * std::f32::consts::PI / sample_rate).sin()
+        (sample_clock * frequency * 2.0 * std::f32::consts::PI / sample_rate).sin()
+    };
+
+    // Or play back the bits of an XO-CHIP pattern as a square wave, looping through all 128 of them.
+    let mut pattern_position = 0f32;
+    let mut next_pattern_sample = move |pattern: AudioPattern| {
+        pattern_position = (pattern_position + pattern.playback_rate() / sample_rate) % 128.0;
+        let bit = pattern_position as usize;
+        if pattern.buffer[bit / 8] >> (7 - bit % 8) & 1 == 1 {
+            0.5
+        } else {
+            -0.5
+        }
     };
 
     let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
@@ -68,7 +89,10 @@ where
     let data_fn = move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
         for frame in data.chunks_mut(channels) {
             let value: T = if volume_switch.load(Ordering::Relaxed) {
-                Sample::from::<f32>(&next_sample())
+                match pattern.load() {
+                    Some(pattern) => Sample::from::<f32>(&next_pattern_sample(pattern)),
+                    None => Sample::from::<f32>(&next_sample()),
+                }
             } else {
                 Sample::from(&0.0)
             };
@@ -0,0 +1,99 @@
+//pausing and changing speed with hotkeys, shared between the event loop and the VM thread
+//the speed is the normal speed doubled or halved a few times, and everything including the timers runs at it
+
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
+
+//how many times the speed can be doubled or halved
+const MAX_SHIFT: i32 = 4;
+//how many times faster holding fast forward runs
+const FAST_FORWARD: f64 = 4.0;
+
+#[derive(Default)]
+pub struct Speed {
+    paused: AtomicBool,
+    //frames left to run while paused
+    advance: AtomicU32,
+    shift: AtomicI32,
+    fast_forward: AtomicBool,
+}
+
+impl Speed {
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn toggle_pause(&self) {
+        let paused = !self.paused();
+        self.advance.store(0, Ordering::Relaxed);
+        self.paused.store(paused, Ordering::Relaxed);
+        log::info!("{}", if paused { "Paused" } else { "Resumed" });
+    }
+
+    //run one more frame, if paused
+    pub fn advance_frame(&self) {
+        if self.paused() {
+            self.advance.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    //whether the next frame should run, which uses up a frame advance if paused
+    pub fn run_frame(&self) -> bool {
+        !self.paused()
+            || self
+                .advance
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok()
+    }
+
+    pub fn double(&self) {
+        self.change(1);
+    }
+
+    pub fn halve(&self) {
+        self.change(-1);
+    }
+
+    fn change(&self, by: i32) {
+        let shift = (self.shift.load(Ordering::Relaxed) + by).clamp(-MAX_SHIFT, MAX_SHIFT);
+        self.shift.store(shift, Ordering::Relaxed);
+        log::info!("Speed is now {}x", 2_f64.powi(shift));
+    }
+
+    pub fn set_fast_forward(&self, fast_forward: bool) {
+        self.fast_forward.store(fast_forward, Ordering::Relaxed);
+    }
+
+    //how many times faster than normal to run
+    pub fn factor(&self) -> f64 {
+        let fast_forward = match self.fast_forward.load(Ordering::Relaxed) {
+            true => FAST_FORWARD,
+            false => 1.0,
+        };
+        2_f64.powi(self.shift.load(Ordering::Relaxed)) * fast_forward
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_speed() {
+        let speed = Speed::default();
+        assert!(speed.run_frame());
+        speed.advance_frame();
+        speed.toggle_pause();
+        assert!(!speed.run_frame());
+        speed.advance_frame();
+        assert!(speed.run_frame());
+        assert!(!speed.run_frame());
+
+        for _ in 0..10 {
+            speed.halve();
+        }
+        assert_eq!(speed.factor(), 1.0 / 16.0);
+        speed.double();
+        speed.set_fast_forward(true);
+        assert_eq!(speed.factor(), 0.5);
+    }
+}
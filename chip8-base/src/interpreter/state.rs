@@ -0,0 +1,28 @@
+use crate::Interpreter;
+use anyhow::{anyhow, Context};
+use std::path::PathBuf;
+
+//save states are kept in numbered slots in the working directory, named so each program has its own
+pub const SLOTS: u8 = 10;
+
+fn slot_path(name: &str, slot: u8) -> PathBuf {
+    PathBuf::from(format!("{name}-slot-{slot}.state"))
+}
+
+pub fn save<I: Interpreter>(interpreter: &I, name: &str, slot: u8) -> anyhow::Result<()> {
+    let state = interpreter
+        .save_state()
+        .ok_or_else(|| anyhow!("This interpreter does not support save states"))?;
+    let path = slot_path(name, slot);
+    std::fs::write(&path, state).with_context(|| format!("Could not write {}", path.display()))
+}
+
+pub fn load<I: Interpreter>(interpreter: &mut I, name: &str, slot: u8) -> anyhow::Result<()> {
+    let path = slot_path(name, slot);
+    let state =
+        std::fs::read(&path).with_context(|| format!("Could not read {}", path.display()))?;
+    interpreter
+        .load_state(&state)
+        .map_err(|e| anyhow!("{e}"))
+        .context("Could not load save state")
+}
@@ -0,0 +1,462 @@
+//running in the terminal instead of a window, for when there's nowhere to open one, like over SSH
+//each character is two pixels stacked up, drawn as ▀ in the top pixel's colour on the bottom pixel's colour,
+//and the display is scaled up to fill the terminal. if the terminal is too small for that, each character is 2x4 pixels of braille
+//
+//terminals only say which keys were typed, so a key is held from when it's pressed until it should have repeated,
+//unless the terminal supports the kitty keyboard protocol and says when keys are released too
+
+use super::vm::{self, Vm, FRAME};
+use super::RunOptions;
+use crate::{keymap, Colour, Display, Interpreter, Palette};
+use anyhow::Context;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Stdout, Write};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use winit::event::VirtualKeyCode;
+
+//how long a key stays held after it's first pressed, which has to cover the delay before it starts repeating
+const HOLD: Duration = Duration::from_millis(500);
+//how long it stays held after each repeat
+const REPEAT_HOLD: Duration = Duration::from_millis(100);
+
+pub fn run<I>(interpreter: I, options: RunOptions) -> anyhow::Result<()>
+where
+    I: Interpreter + Send + 'static,
+{
+    log::info!("Initalising terminal...");
+    let _raw = RawTerminal::enter().context("Could not set up the terminal")?;
+    let (columns, rows) = terminal::size().context("Could not get the size of the terminal")?;
+    let mut screen = Screen::new(columns, rows);
+
+    let mut vm = Vm::start(interpreter, &options)?;
+    let result = event_loop(&mut vm, &mut screen, &options);
+    vm.stop();
+    result
+}
+
+fn event_loop(vm: &mut Vm, screen: &mut Screen, options: &RunOptions) -> anyhow::Result<()> {
+    let mut held = Held::default();
+    let mut title = String::new();
+    loop {
+        //if cpu thread has exited (due to an error, panic or quitting), return why
+        if let Some(finished) = vm.finished() {
+            vm.exit(options);
+            return finished;
+        }
+
+        //handle input until it's time to draw the next frame
+        let next_frame = Instant::now() + FRAME;
+        while event::poll(next_frame.saturating_duration_since(Instant::now()))? {
+            match event::read()? {
+                Event::Key(event) => {
+                    //raw mode stops Ctrl-C from interrupting, so it quits like Escape does
+                    let close_requested = event.kind == KeyEventKind::Press
+                        && (event.code == KeyCode::Char('c')
+                            && event.modifiers.contains(KeyModifiers::CONTROL)
+                            || options.escape_quits && event.code == KeyCode::Esc);
+                    if close_requested
+                        && options
+                            .on_close
+                            .as_ref()
+                            .is_none_or(|handler| (handler.0)())
+                    {
+                        vm.exit(options);
+                        return Ok(());
+                    }
+                    let Some(key) = virtual_key(&event) else {
+                        continue;
+                    };
+                    match event.kind {
+                        KeyEventKind::Release => held.release(key),
+                        kind => {
+                            if kind == KeyEventKind::Press && vm::HOTKEYS.contains(&key) {
+                                vm.hotkey(key);
+                            }
+                            held.press(key, Instant::now());
+                        }
+                    }
+                }
+                Event::Resize(columns, rows) => screen.resize(columns, rows),
+                _ => (),
+            }
+        }
+
+        let now = Instant::now();
+        vm.rewinding
+            .store(held.held(VirtualKeyCode::Back, now), Ordering::Relaxed);
+        vm.speed
+            .set_fast_forward(held.held(VirtualKeyCode::Tab, now));
+        vm.input_buffer
+            .store(options.keymap.state(|key| held.held(key, now)));
+
+        screen
+            .draw(&vm.frame_buffer.load().0, &options.palette)
+            .context("Could not draw to the terminal")?;
+        let new_title = vm.title(&options.title);
+        if new_title != title {
+            execute!(io::stdout(), terminal::SetTitle(&new_title)).ok();
+            title = new_title;
+        }
+    }
+}
+
+//puts the terminal into raw mode on the alternate screen, and puts it back how it was when dropped
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let raw = RawTerminal;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        //terminals that don't understand this ignore it, and only report key presses
+        execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )
+        .ok();
+        Ok(raw)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        execute!(
+            io::stdout(),
+            PopKeyboardEnhancementFlags,
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        )
+        .ok();
+        terminal::disable_raw_mode().ok();
+    }
+}
+
+//the key on the keyboard for a key event from the terminal, if it's one that can be used
+fn virtual_key(event: &KeyEvent) -> Option<VirtualKeyCode> {
+    let keypad = event.state.contains(KeyEventState::KEYPAD);
+    Some(match event.code {
+        KeyCode::Char(c) if keypad => match c {
+            '0'..='9' => [
+                VirtualKeyCode::Numpad0,
+                VirtualKeyCode::Numpad1,
+                VirtualKeyCode::Numpad2,
+                VirtualKeyCode::Numpad3,
+                VirtualKeyCode::Numpad4,
+                VirtualKeyCode::Numpad5,
+                VirtualKeyCode::Numpad6,
+                VirtualKeyCode::Numpad7,
+                VirtualKeyCode::Numpad8,
+                VirtualKeyCode::Numpad9,
+            ][c as usize - '0' as usize],
+            '+' => VirtualKeyCode::NumpadAdd,
+            '-' => VirtualKeyCode::NumpadSubtract,
+            '*' => VirtualKeyCode::NumpadMultiply,
+            '/' => VirtualKeyCode::NumpadDivide,
+            '.' => VirtualKeyCode::NumpadDecimal,
+            _ => return None,
+        },
+        KeyCode::Char(c) => keymap::typed(c)?,
+        KeyCode::Enter if keypad => VirtualKeyCode::NumpadEnter,
+        KeyCode::Enter => VirtualKeyCode::Return,
+        KeyCode::Backspace => VirtualKeyCode::Back,
+        KeyCode::Tab => VirtualKeyCode::Tab,
+        KeyCode::Esc => VirtualKeyCode::Escape,
+        KeyCode::Up => VirtualKeyCode::Up,
+        KeyCode::Down => VirtualKeyCode::Down,
+        KeyCode::Left => VirtualKeyCode::Left,
+        KeyCode::Right => VirtualKeyCode::Right,
+        KeyCode::PageUp => VirtualKeyCode::PageUp,
+        KeyCode::PageDown => VirtualKeyCode::PageDown,
+        KeyCode::Insert => VirtualKeyCode::Insert,
+        KeyCode::Delete => VirtualKeyCode::Delete,
+        KeyCode::F(n) => [
+            VirtualKeyCode::F1,
+            VirtualKeyCode::F2,
+            VirtualKeyCode::F3,
+            VirtualKeyCode::F4,
+            VirtualKeyCode::F5,
+            VirtualKeyCode::F6,
+            VirtualKeyCode::F7,
+            VirtualKeyCode::F8,
+            VirtualKeyCode::F9,
+            VirtualKeyCode::F10,
+            VirtualKeyCode::F11,
+            VirtualKeyCode::F12,
+        ]
+        .get(usize::from(n).checked_sub(1)?)
+        .copied()?,
+        _ => return None,
+    })
+}
+
+//which keys are held, as far as the terminal lets us tell
+#[derive(Default)]
+struct Held {
+    //when each key that's been pressed will be let go of, or None to wait for it to be released
+    keys: HashMap<VirtualKeyCode, Option<Instant>>,
+    //set once the terminal reports a key being released, after which keys are held until they are
+    releases: bool,
+}
+
+impl Held {
+    fn press(&mut self, key: VirtualKeyCode, now: Instant) {
+        let until = match self.held(key, now) {
+            _ if self.releases => None,
+            true => Some(now + REPEAT_HOLD),
+            false => Some(now + HOLD),
+        };
+        //a repeat shouldn't cut short the hold from the first press
+        let until = match self.keys.get(&key) {
+            Some(Some(old)) => until.map(|until| until.max(*old)),
+            _ => until,
+        };
+        self.keys.insert(key, until);
+    }
+
+    fn release(&mut self, key: VirtualKeyCode) {
+        self.releases = true;
+        self.keys.remove(&key);
+    }
+
+    fn held(&self, key: VirtualKeyCode, now: Instant) -> bool {
+        self.keys
+            .get(&key)
+            .is_some_and(|until| until.is_none_or(|until| now < until))
+    }
+}
+
+//a character on the terminal, and its colours
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Cell {
+    c: char,
+    foreground: Colour,
+    background: Colour,
+}
+
+//the display as a grid of characters to fill a terminal, or None if it's too small to fit even as braille
+fn render(
+    display: &Display,
+    palette: &Palette,
+    columns: usize,
+    rows: usize,
+) -> Option<Vec<Vec<Cell>>> {
+    let (width, height) = (display.width(), display.height());
+    //as many terminal pixels to each display pixel as fit, with two terminal pixels to a character
+    let scale = (columns / width).min(rows * 2 / height);
+    if scale > 0 {
+        let colour =
+            |x: usize, y: usize| palette.colours[display.colour(x / scale, y / scale) as usize];
+        let grid = (0..height * scale / 2)
+            .map(|row| {
+                (0..width * scale)
+                    .map(|x| Cell {
+                        c: '▀',
+                        foreground: colour(x, row * 2),
+                        background: colour(x, row * 2 + 1),
+                    })
+                    .collect()
+            })
+            .collect();
+        return Some(grid);
+    }
+    if columns < width / 2 || rows < height / 4 {
+        return None;
+    }
+    //braille has one colour, so a character with any pixels set takes the colour of the highest one
+    let grid = (0..height / 4)
+        .map(|row| {
+            (0..width / 2)
+                .map(|column| {
+                    let (mut dots, mut colour) = (0, 0);
+                    for (dot, (x, y)) in BRAILLE_DOTS.iter().enumerate() {
+                        let c = display.colour(column * 2 + x, row * 4 + y);
+                        if c != 0 {
+                            dots |= 1 << dot;
+                            colour = colour.max(c);
+                        }
+                    }
+                    Cell {
+                        c: char::from_u32(0x2800 + dots).unwrap(),
+                        foreground: palette.colours[colour as usize],
+                        background: palette.background(),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Some(grid)
+}
+
+//the position in a 2x4 block of each dot of a braille character, in the order of their bits
+const BRAILLE_DOTS: [(usize, usize); 8] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (0, 3),
+    (1, 3),
+];
+
+//draws displays to the terminal, only writing the characters that changed since the last frame to keep it quick over SSH
+struct Screen {
+    out: BufWriter<Stdout>,
+    columns: usize,
+    rows: usize,
+    //what's on the terminal now, which is empty if it needs to be drawn from scratch
+    drawn: Vec<Vec<Cell>>,
+    too_small: bool,
+}
+
+impl Screen {
+    fn new(columns: u16, rows: u16) -> Self {
+        Screen {
+            out: BufWriter::new(io::stdout()),
+            columns: columns.into(),
+            rows: rows.into(),
+            drawn: Vec::new(),
+            too_small: false,
+        }
+    }
+
+    fn resize(&mut self, columns: u16, rows: u16) {
+        self.columns = columns.into();
+        self.rows = rows.into();
+        self.drawn.clear();
+        self.too_small = false;
+    }
+
+    fn draw(&mut self, display: &Display, palette: &Palette) -> io::Result<()> {
+        let Some(grid) = render(display, palette, self.columns, self.rows) else {
+            if !self.too_small {
+                self.too_small = true;
+                self.drawn.clear();
+                queue!(
+                    self.out,
+                    ResetColor,
+                    terminal::Clear(terminal::ClearType::All),
+                    cursor::MoveTo(0, 0),
+                    Print(format!(
+                        "Make the terminal at least {}x{} to see the display",
+                        display.width() / 2,
+                        display.height() / 4
+                    ))
+                )?;
+            }
+            return self.out.flush();
+        };
+        self.too_small = false;
+        //the size changes with the resolution, so the edges need clearing
+        if self.drawn.len() != grid.len() || self.drawn[0].len() != grid[0].len() {
+            self.drawn.clear();
+            queue!(
+                self.out,
+                ResetColor,
+                terminal::Clear(terminal::ClearType::All)
+            )?;
+        }
+
+        //centred in the terminal
+        let (left, top) = (
+            (self.columns - grid[0].len()) / 2,
+            (self.rows - grid.len()) / 2,
+        );
+        //where the cursor is and what colours are set, so they're only changed when they need to be
+        let mut cursor = None;
+        let mut colours = None;
+        for (y, row) in grid.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if self.drawn.get(y).and_then(|row| row.get(x)) == Some(cell) {
+                    continue;
+                }
+                if cursor != Some((x, y)) {
+                    queue!(
+                        self.out,
+                        cursor::MoveTo((left + x) as u16, (top + y) as u16)
+                    )?;
+                }
+                if colours != Some((cell.foreground, cell.background)) {
+                    let [r, g, b] = cell.foreground;
+                    queue!(self.out, SetForegroundColor(Color::Rgb { r, g, b }))?;
+                    let [r, g, b] = cell.background;
+                    queue!(self.out, SetBackgroundColor(Color::Rgb { r, g, b }))?;
+                    colours = Some((cell.foreground, cell.background));
+                }
+                queue!(self.out, Print(cell.c))?;
+                cursor = Some((x + 1, y));
+            }
+        }
+        self.drawn = grid;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Pixel, Resolution};
+
+    #[test]
+    fn test_render() {
+        let mut display = Display::new(Resolution::Low);
+        display.row_mut(0, 1)[0] = Pixel::White;
+        let palette = Palette::MONOCHROME;
+        let (black, white) = (palette.background(), palette.foreground());
+
+        //a low resolution display fits twice over in a terminal big enough for high resolution
+        let grid = render(&display, &palette, 130, 40).unwrap();
+        assert_eq!((grid[0].len(), grid.len()), (128, 32));
+        assert_eq!(
+            (grid[0][0].foreground, grid[0][0].background),
+            (black, black)
+        );
+        assert_eq!(
+            (grid[1][0].foreground, grid[1][0].background),
+            (white, white)
+        );
+        assert_eq!(grid[1][2].background, black);
+
+        //too small for half blocks, so it falls back to braille
+        let grid = render(&display, &palette, 40, 10).unwrap();
+        assert_eq!((grid[0].len(), grid.len()), (32, 8));
+        assert_eq!(grid[0][0].c, '\u{2802}');
+        assert_eq!(grid[0][0].foreground, white);
+        assert_eq!(grid[0][1].c, '\u{2800}');
+
+        assert!(render(&display, &palette, 20, 10).is_none());
+    }
+
+    #[test]
+    fn test_held() {
+        let mut held = Held::default();
+        let now = Instant::now();
+        held.press(VirtualKeyCode::Q, now);
+        assert!(held.held(VirtualKeyCode::Q, now + HOLD / 2));
+        assert!(!held.held(VirtualKeyCode::Q, now + HOLD));
+        assert!(!held.held(VirtualKeyCode::W, now));
+
+        //once the terminal reports releases, keys stay held until they're released
+        held.release(VirtualKeyCode::Q);
+        held.press(VirtualKeyCode::W, now);
+        assert!(held.held(VirtualKeyCode::W, now + HOLD * 10));
+        held.release(VirtualKeyCode::W);
+        assert!(!held.held(VirtualKeyCode::W, now));
+    }
+}
@@ -0,0 +1,395 @@
+//the interpreter runs on its own thread, which both frontends drive the same way:
+//they hand it keys through input_buffer, take frames from frame_buffer, and send it hotkeys
+
+use super::speed::Speed;
+use super::{capture::Capture, rewind, sound, state, RunOptions};
+use crate::movie::{Player, Recorder};
+use crate::{Display, Error, Interpreter, Keys};
+use anyhow::{anyhow, Context};
+use crossbeam::atomic::AtomicCell;
+use crossbeam::channel::{self, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use winit::event::VirtualKeyCode;
+
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+//if the interpreter falls further behind than this, the timers give up catching up rather than racing to
+const MAX_LAG: Duration = Duration::from_millis(250);
+
+//the keys that trigger something the moment they're pressed, handled by Vm::hotkey
+pub const HOTKEYS: [VirtualKeyCode; 10] = [
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F9,
+    VirtualKeyCode::F10,
+    VirtualKeyCode::F12,
+    VirtualKeyCode::PageUp,
+    VirtualKeyCode::PageDown,
+];
+
+//commands sent from the frontend to the VM thread in response to hotkeys
+enum Command {
+    SaveState(u8),
+    LoadState(u8),
+}
+
+pub struct Vm {
+    //include a flag so we know if the current frame has been drawn, to avoid drawing it twice
+    pub frame_buffer: Arc<AtomicCell<(Display, bool)>>,
+    pub input_buffer: Arc<AtomicCell<Keys>>,
+    pub rewinding: Arc<AtomicBool>,
+    pub speed: Arc<Speed>,
+    pub capture: Arc<Mutex<Capture>>,
+    //set when the frontend closes, to stop the VM thread
+    stop: Arc<AtomicBool>,
+    commands: Sender<Command>,
+    handle: Option<JoinHandle<Result<(), Error>>>,
+    //instructions a second at normal speed, for the title
+    rate: f64,
+    //instructions a second over the last second when uncapped, for the title
+    measured: Arc<AtomicCell<f64>>,
+    uncapped: bool,
+    slot: u8,
+}
+
+impl Vm {
+    //start running the interpreter, once the frontend is ready to show it
+    pub fn start<I>(mut interpreter: I, options: &RunOptions) -> anyhow::Result<Self>
+    where
+        I: Interpreter + Send + 'static,
+    {
+        //a movie has to start from its saved state, and a recording from the state the interpreter is in now
+        if let Some(state) = options
+            .replay
+            .as_ref()
+            .and_then(|movie| movie.state.as_ref())
+        {
+            interpreter
+                .load_state(state)
+                .map_err(|e| anyhow!("{e}"))
+                .context("Could not load the movie's starting state")?;
+        }
+        let mut player = options.replay.as_ref().map(Player::new);
+        //movies tick the timers every ipf cycles instead of in real time, so they replay exactly, and need to keep it the same
+        let ipf = options
+            .replay
+            .as_ref()
+            .and_then(|movie| movie.instructions_per_frame)
+            .or(options.instructions_per_frame)
+            .unwrap_or_else(|| crate::cycles_per_frame(interpreter.speed()));
+        let cycle_time = match options.instructions_per_frame {
+            Some(n) => FRAME / n,
+            None => interpreter.speed(),
+        };
+        let mut recorder = match &options.record {
+            Some(path) => Some(
+                Recorder::create(path, ipf, interpreter.save_state().as_deref())
+                    .with_context(|| format!("Could not create movie {}", path.display()))?,
+            ),
+            None => None,
+        };
+        //movies need every cycle to run exactly as it was recorded, so nothing can jump around in time
+        let movie = player.is_some() || recorder.is_some();
+        //which, like running uncapped, means the timers tick on a count of cycles instead of in real time
+        let uncapped = options.uncapped;
+        let counted = movie || uncapped;
+
+        let (commands, command_rx) = channel::unbounded();
+        let vm = Vm {
+            frame_buffer: Arc::new(AtomicCell::new((Display::default(), false))),
+            input_buffer: Arc::new(AtomicCell::new([false; 16])),
+            rewinding: Arc::new(AtomicBool::new(false)),
+            speed: Arc::new(Speed::default()),
+            capture: Arc::new(Mutex::new(Capture::new(
+                options.palette,
+                options.capture_scale,
+            ))),
+            stop: Arc::new(AtomicBool::new(false)),
+            commands,
+            handle: None,
+            rate: match options.frame_batching {
+                true => f64::from(ipf) * 60.0,
+                false => 1.0 / cycle_time.as_secs_f64(),
+            },
+            measured: Arc::new(AtomicCell::new(0.0)),
+            uncapped,
+            slot: 0,
+        };
+
+        let handle = thread::Builder::new().name("VM Executor".to_string()).spawn({
+            //make copies of what we need
+            let frame_buffer = vm.frame_buffer.clone();
+            let input_buffer = vm.input_buffer.clone();
+            let rewinding = vm.rewinding.clone();
+            let stop = vm.stop.clone();
+            let speed = vm.speed.clone();
+            let capture = vm.capture.clone();
+            let measured = vm.measured.clone();
+            let (audio, frequency, rewind_depth, batch) = (
+                options.audio,
+                options.buzzer_frequency,
+                options.rewind_depth,
+                options.frame_batching,
+            );
+            let states = options.save_states.clone();
+
+            //start thread
+            move || {
+                //init the audio on the thread because cpal::stream:  !send
+                let buzzer = if audio {
+                    log::info!("Initalising audio components...");
+                    sound::Buzzer::init(frequency)
+                        .map_err(|e| {
+                            log::error!("Failure in initalising audio: {e:?}. Continuing with no sound.")
+                        })
+                        .ok()
+                } else {
+                    None
+                };
+
+                log::info!("Starting CPU...");
+                let mut rewind = rewind::Rewind::new(if movie { 0 } else { rewind_depth });
+                let mut next_frame = Instant::now() + FRAME;
+                let mut cycle = 0;
+                //whether the current frame is paused
+                let mut frozen = false;
+                //when the rate was last measured, and the cycle count then
+                let mut measured_at = (Instant::now(), 0);
+                while !stop.load(Ordering::Relaxed) && !interpreter.quit_requested() {
+                    let t0 = Instant::now();
+                    handle_commands(&mut interpreter, &command_rx, movie, &states);
+                    //changing the speed stretches or squashes frames, so the timers change speed with everything else
+                    let factor = speed.factor();
+                    let frame = FRAME.div_f64(factor);
+
+                    //once a frame, either record the state so we can rewind to it later, or go back a frame if rewinding
+                    let rewinding = rewinding.load(Ordering::Relaxed) && !movie;
+                    if t0 >= next_frame {
+                        next_frame += frame;
+                        if t0 > next_frame + MAX_LAG {
+                            next_frame = t0 + frame;
+                        }
+                        frozen = !speed.run_frame();
+                        //the timers count down in real time, however fast instructions are running
+                        if !rewinding && !counted && !frozen {
+                            interpreter.timer_tick();
+                        }
+                        if let Some(Err(e)) = recorder.as_mut().map(Recorder::flush) {
+                            log::error!("Could not write movie, stopping recording: {e}");
+                            recorder = None;
+                        }
+                        if rewinding {
+                            capture.lock().unwrap().frame(&frame_buffer.load().0);
+                            if let Some(display) = rewind.pop(&mut interpreter) {
+                                frame_buffer.store((display, false));
+                            }
+                        } else if !frozen {
+                            capture.lock().unwrap().frame(&frame_buffer.load().0);
+                            rewind.push(&interpreter, &frame_buffer.load().0);
+                        }
+                    }
+
+                    //step the cpu, read input buffer, write to framebuffer
+                    //errors stop the interpreter, and are handed back to the frontend to report
+                    //in batches, a frame's worth of instructions run at once and the display is presented after them all
+                    let steps = if rewinding || frozen {
+                        0
+                    } else if batch || uncapped {
+                        ipf
+                    } else {
+                        1
+                    };
+                    let mut update = None;
+                    for _ in 0..steps {
+                        //nothing happens until the next frame once the interpreter is waiting for it, so stop early
+                        //movies have to spend the cycles, as their timers tick on a cycle count
+                        if !counted && interpreter.waiting_for_vblank() {
+                            break;
+                        }
+                        let keys = match &mut player {
+                            Some(player) => player.keys(cycle),
+                            None => input_buffer.load(),
+                        };
+                        if let Some(Err(e)) = recorder.as_mut().map(|r| r.record(cycle, &keys)) {
+                            log::error!("Could not write movie, stopping recording: {e}");
+                            recorder = None;
+                        }
+                        cycle += 1;
+                        match interpreter.step(&keys) {
+                            Ok(Some(display)) => update = Some(display),
+                            Ok(None) => (),
+                            Err(e) => {
+                                if let Some(Err(e)) = recorder.as_mut().map(Recorder::flush) {
+                                    log::error!("Could not write movie: {e}");
+                                }
+                                return Err(e);
+                            }
+                        }
+                        if counted && cycle % u64::from(ipf) == 0 {
+                            interpreter.timer_tick();
+                        }
+                    }
+                    if let Some(display) = update {
+                        frame_buffer.store((display, false));
+                    }
+
+                    //handle sound
+                    if let Some(buzzer) = &buzzer {
+                        let active = interpreter.buzzer_active() && !rewinding && !frozen;
+                        buzzer.switch.store(active, Ordering::Relaxed);
+                        buzzer.pattern.store(interpreter.audio_pattern());
+                    }
+
+                    let elapsed = t0 - measured_at.0;
+                    if elapsed >= Duration::from_secs(1) {
+                        let rate = (cycle - measured_at.1) as f64 / elapsed.as_secs_f64();
+                        measured.store(rate);
+                        if uncapped {
+                            log::info!("Running {rate:.0} instructions a second");
+                        }
+                        measured_at = (t0, cycle);
+                    }
+                    //uncapped, the next batch runs straight away, unless there was nothing to run
+                    if uncapped && steps != 0 {
+                        continue;
+                    }
+
+                    //sleep to make time steps uniform, until the next frame if running in batches
+                    let step_time = if batch || uncapped {
+                        next_frame.saturating_duration_since(t0)
+                    } else {
+                        cycle_time.div_f64(factor)
+                    };
+                    if let Some(sleepy_time) = step_time.checked_sub(Instant::now() - t0) {
+                        thread::sleep(sleepy_time);
+                        log::debug!(
+                            "Took {:?} to execute {steps} instructions",
+                            step_time - sleepy_time
+                        )
+                    } else {
+                        log::warn!("CPU clock is running slow, your interpreter is taking too long to execute instructions.")
+                    }
+                }
+                if let Some(Err(e)) = recorder.as_mut().map(Recorder::flush) {
+                    log::error!("Could not write movie: {e}");
+                }
+                Ok(())
+            }
+        }).context("Could not start VM execution thread")?;
+
+        Ok(Vm {
+            handle: Some(handle),
+            ..vm
+        })
+    }
+
+    //once the VM thread has stopped by itself, because of an error, a panic or the interpreter quitting, why it did
+    pub fn finished(&mut self) -> Option<anyhow::Result<()>> {
+        let handle = self.handle.take_if(|h| h.is_finished())?;
+        Some(match handle.join() {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(anyhow::Error::new(e).context("Interpreter stopped")),
+            Err(_) => Err(anyhow!("VM thread panicked, shutting down...")),
+        })
+    }
+
+    //stop the interpreter if it's still running, and wait for it to finish
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                log::error!("VM thread panicked while shutting down");
+            }
+        }
+    }
+
+    //save any recording that's in progress, and the final screenshot if one was asked for
+    pub fn exit(&self, options: &RunOptions) {
+        self.capture.lock().unwrap().finish();
+        if let Some(path) = &options.exit_screenshot {
+            let display = self.frame_buffer.load().0;
+            match crate::save_png(&display, &options.palette, options.capture_scale, path) {
+                Ok(()) => log::info!("Saved screenshot to {}", path.display()),
+                Err(e) => log::error!("Could not write {}: {e}", path.display()),
+            }
+        }
+    }
+
+    //respond to one of the HOTKEYS being pressed
+    pub fn hotkey(&mut self, key: VirtualKeyCode) {
+        match key {
+            //capture hotkeys
+            VirtualKeyCode::F12 => {
+                if let Err(e) = self
+                    .capture
+                    .lock()
+                    .unwrap()
+                    .screenshot(&self.frame_buffer.load().0)
+                {
+                    log::error!("{e:#}");
+                }
+            }
+            VirtualKeyCode::F10 => self.capture.lock().unwrap().toggle_recording(),
+            //save state hotkeys
+            VirtualKeyCode::F6 => {
+                self.slot = (self.slot + state::SLOTS - 1) % state::SLOTS;
+                log::info!("Selected save state slot {}", self.slot);
+            }
+            VirtualKeyCode::F7 => {
+                self.slot = (self.slot + 1) % state::SLOTS;
+                log::info!("Selected save state slot {}", self.slot);
+            }
+            VirtualKeyCode::F5 => {
+                self.commands.send(Command::SaveState(self.slot)).ok();
+            }
+            VirtualKeyCode::F9 => {
+                self.commands.send(Command::LoadState(self.slot)).ok();
+            }
+            //speed hotkeys
+            VirtualKeyCode::F2 => self.speed.toggle_pause(),
+            VirtualKeyCode::F3 => self.speed.advance_frame(),
+            VirtualKeyCode::PageUp => self.speed.double(),
+            VirtualKeyCode::PageDown => self.speed.halve(),
+            _ => (),
+        }
+    }
+
+    //the title with the current speed after it
+    pub fn title(&self, title: &str) -> String {
+        if self.speed.paused() {
+            format!("{title} - paused")
+        } else if self.uncapped {
+            format!("{title} - {:.0} Hz uncapped", self.measured.load())
+        } else {
+            format!("{title} - {:.0} Hz", self.rate * self.speed.factor())
+        }
+    }
+}
+
+//run any commands sent from the frontend, between instructions
+fn handle_commands<I: Interpreter>(
+    interpreter: &mut I,
+    commands: &Receiver<Command>,
+    movie: bool,
+    states: &str,
+) {
+    for command in commands.try_iter() {
+        let result = match command {
+            Command::SaveState(slot) => state::save(interpreter, states, slot)
+                .map(|_| log::info!("Saved state to slot {slot}")),
+            Command::LoadState(_) if movie => Err(anyhow!(
+                "Save states can't be loaded while recording or replaying a movie"
+            )),
+            Command::LoadState(slot) => state::load(interpreter, states, slot)
+                .map(|_| log::info!("Loaded state from slot {slot}")),
+        };
+        if let Err(e) = result {
+            log::error!("{e:#}");
+        }
+    }
+}
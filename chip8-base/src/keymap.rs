@@ -0,0 +1,246 @@
+use std::fmt;
+use std::str::FromStr;
+use winit::event::VirtualKeyCode;
+use VirtualKeyCode::*;
+
+//the CHIP-8 keypad, in the order its keys are laid out on the keyboard
+// 1 2 3 C
+// 4 5 6 D
+// 7 8 9 E
+// A 0 B F
+pub(crate) const KEYPAD: [u8; 16] = [
+    0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+];
+
+//the keys in the same place on each of the keyboard layouts in Keymap::LAYOUTS, in keypad order
+#[rustfmt::skip]
+const LAYOUT_KEYS: [[VirtualKeyCode; 16]; 5] = [
+    [Key1, Key2, Key3, Key4, Q, W, E, R, A, S, D, F, Z, X, C, V],
+    [Key1, Key2, Key3, Key4, A, Z, E, R, Q, S, D, F, W, X, C, V],
+    [Key1, Key2, Key3, Key4, Q, W, E, R, A, S, D, F, Y, X, C, V],
+    [Key1, Key2, Key3, Key4, Apostrophe, Comma, Period, P, A, O, E, U, Semicolon, Q, J, K],
+    [Key1, Key2, Key3, Key4, Q, W, F, P, A, R, S, T, Z, X, C, D],
+];
+
+//the names keys are given by in keymaps, and the labels shown for them in the overlay
+#[rustfmt::skip]
+const KEY_NAMES: &[(&str, &str, VirtualKeyCode)] = &[
+    ("0", "0", Key0), ("1", "1", Key1), ("2", "2", Key2), ("3", "3", Key3), ("4", "4", Key4),
+    ("5", "5", Key5), ("6", "6", Key6), ("7", "7", Key7), ("8", "8", Key8), ("9", "9", Key9),
+    ("A", "A", A), ("B", "B", B), ("C", "C", C), ("D", "D", D), ("E", "E", E), ("F", "F", F),
+    ("G", "G", G), ("H", "H", H), ("I", "I", I), ("J", "J", J), ("K", "K", K), ("L", "L", L),
+    ("M", "M", M), ("N", "N", N), ("O", "O", O), ("P", "P", P), ("Q", "Q", Q), ("R", "R", R),
+    ("S", "S", S), ("T", "T", T), ("U", "U", U), ("V", "V", V), ("W", "W", W), ("X", "X", X),
+    ("Y", "Y", Y), ("Z", "Z", Z),
+    ("Numpad0", "NUM0", Numpad0), ("Numpad1", "NUM1", Numpad1), ("Numpad2", "NUM2", Numpad2),
+    ("Numpad3", "NUM3", Numpad3), ("Numpad4", "NUM4", Numpad4), ("Numpad5", "NUM5", Numpad5),
+    ("Numpad6", "NUM6", Numpad6), ("Numpad7", "NUM7", Numpad7), ("Numpad8", "NUM8", Numpad8),
+    ("Numpad9", "NUM9", Numpad9), ("NumpadAdd", "NUM+", NumpadAdd),
+    ("NumpadSubtract", "NUM-", NumpadSubtract), ("NumpadMultiply", "NUM*", NumpadMultiply),
+    ("NumpadDivide", "NUM/", NumpadDivide), ("NumpadDecimal", "NUM.", NumpadDecimal),
+    ("NumpadEnter", "NUMENT", NumpadEnter),
+    ("Up", "UP", Up), ("Down", "DOWN", Down), ("Left", "LEFT", Left), ("Right", "RIGHT", Right),
+    ("Space", "SPACE", Space), ("Enter", "ENTER", Return), ("Tab", "TAB", Tab),
+    ("LShift", "LSHIFT", LShift), ("RShift", "RSHIFT", RShift),
+    ("LCtrl", "LCTRL", LControl), ("RCtrl", "RCTRL", RControl),
+    ("LAlt", "LALT", LAlt), ("RAlt", "RALT", RAlt),
+    ("Comma", ",", Comma), ("Period", ".", Period), ("Semicolon", ";", Semicolon),
+    ("Slash", "/", Slash), ("Backslash", "\\", Backslash), ("Apostrophe", "'", Apostrophe),
+    ("Grave", "`", Grave), ("LBracket", "[", LBracket), ("RBracket", "]", RBracket),
+    ("Minus", "-", Minus), ("Equals", "=", Equals),
+    ("Insert", "INS", Insert), ("Delete", "DEL", Delete),
+];
+
+//keys that run uses as hotkeys, which can be named but not bound, and what they do
+const RESERVED: &[(VirtualKeyCode, &str)] = &[(Tab, "fast forward")];
+
+/// Which keys on the keyboard press each of the 16 CHIP-8 keys.
+///
+/// The default is the 4x4 block on the left of a QWERTY keyboard, `1234`/`QWER`/`ASDF`/`ZXCV`.
+/// There are built in keymaps for the same block on other [`LAYOUTS`][Keymap::LAYOUTS],
+/// and any CHIP-8 key can be bound to as many keys as you like, by name.
+///
+/// Keymaps can be parsed from a string, which is an optional layout followed by comma separated bindings.
+/// Each binding is a CHIP-8 key in hex, then `=` and the keys for it separated by `/`, replacing what the layout had for it.
+/// For example `azerty,5=Z/Up,8=S/Down` uses the AZERTY block, but also lets the arrow keys press 5 and 8.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    keys: [Vec<VirtualKeyCode>; 16],
+}
+
+impl Keymap {
+    /// The names of the built in keyboard layouts
+    pub const LAYOUTS: [&'static str; 5] = ["qwerty", "azerty", "qwertz", "dvorak", "colemak"];
+
+    /// A keymap with no keys bound at all
+    pub fn empty() -> Self {
+        Keymap {
+            keys: Default::default(),
+        }
+    }
+
+    /// The 4x4 block on the left of a keyboard layout, looked up by name ignoring case
+    pub fn layout(name: &str) -> Option<Self> {
+        let layout = Self::LAYOUTS
+            .iter()
+            .position(|layout| layout.eq_ignore_ascii_case(name))?;
+        let mut keymap = Keymap::empty();
+        for (chip8_key, key) in KEYPAD.iter().zip(&LAYOUT_KEYS[layout]) {
+            keymap.keys[*chip8_key as usize].push(*key);
+        }
+        Some(keymap)
+    }
+
+    /// Adds a key to the keys that press a CHIP-8 key.
+    /// Keys are named by what's printed on them, like `Q`, `7` or `Space`, and see the source for the full list.
+    /// Keys that [`run`][crate::run] uses as hotkeys can't be bound.
+    pub fn bind(&mut self, chip8_key: u8, name: &str) -> Result<(), String> {
+        let key = KEY_NAMES
+            .iter()
+            .find(|(n, _, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, _, key)| *key)
+            .ok_or_else(|| format!("Unknown key: {name}"))?;
+        if let Some((_, hotkey)) = RESERVED.iter().find(|(k, _)| *k == key) {
+            return Err(format!("{} is reserved for {hotkey}", lookup(key).0));
+        }
+        self.keys[chip8_key as usize & 0xF].push(key);
+        Ok(())
+    }
+
+    /// Removes all the keys bound to a CHIP-8 key
+    pub fn unbind(&mut self, chip8_key: u8) {
+        self.keys[chip8_key as usize & 0xF].clear();
+    }
+
+    /// The names of the keys that press a CHIP-8 key
+    pub fn bindings(&self, chip8_key: u8) -> impl Iterator<Item = &'static str> + '_ {
+        self.keys[chip8_key as usize & 0xF]
+            .iter()
+            .map(|key| lookup(*key).0)
+    }
+
+    //the short labels for the keys that press a CHIP-8 key, for the overlay
+    pub(crate) fn labels(&self, chip8_key: u8) -> impl Iterator<Item = &'static str> + '_ {
+        self.keys[chip8_key as usize & 0xF]
+            .iter()
+            .map(|key| lookup(*key).1)
+    }
+
+    //whether each CHIP-8 key is pressed, given a test for whether a key on the keyboard is held
+    pub(crate) fn state(&self, held: impl Fn(VirtualKeyCode) -> bool) -> crate::Keys {
+        std::array::from_fn(|n| self.keys[n].iter().any(|key| held(*key)))
+    }
+}
+
+//the key that types a character, for terminals which only say what was typed
+pub(crate) fn typed(c: char) -> Option<VirtualKeyCode> {
+    if c == ' ' {
+        return Some(Space);
+    }
+    let mut label = [0; 4];
+    let label = c.to_ascii_uppercase().encode_utf8(&mut label);
+    KEY_NAMES
+        .iter()
+        .find(|(_, l, _)| *l == label)
+        .map(|(_, _, key)| *key)
+}
+
+fn lookup(key: VirtualKeyCode) -> (&'static str, &'static str) {
+    KEY_NAMES
+        .iter()
+        .find(|(_, _, k)| *k == key)
+        .map(|(name, label, _)| (*name, *label))
+        .expect("every key in a keymap has a name")
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::layout("qwerty").unwrap()
+    }
+}
+
+impl FromStr for Keymap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',').map(str::trim).peekable();
+        let mut keymap = match parts.peek().and_then(|layout| Keymap::layout(layout)) {
+            Some(keymap) => {
+                parts.next();
+                keymap
+            }
+            None => Keymap::default(),
+        };
+        for binding in parts {
+            let (chip8_key, names) = binding.split_once('=').ok_or_else(|| {
+                format!(
+                    "Keymaps are one of {} followed by bindings like `5=W/Up`, not `{binding}`",
+                    Keymap::LAYOUTS.join(", ")
+                )
+            })?;
+            let chip8_key = u8::from_str_radix(chip8_key.trim(), 16)
+                .ok()
+                .filter(|k| *k < 16)
+                .ok_or_else(|| format!("Invalid CHIP-8 key: {chip8_key}"))?;
+            keymap.unbind(chip8_key);
+            for name in names.split('/').map(str::trim).filter(|n| !n.is_empty()) {
+                keymap.bind(chip8_key, name)?;
+            }
+        }
+        Ok(keymap)
+    }
+}
+
+/// Prints every binding, in a form that can be parsed back
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bindings: Vec<String> = (0..16)
+            .map(|k| format!("{k:X}={}", self.bindings(k).collect::<Vec<_>>().join("/")))
+            .collect();
+        write!(f, "{}", bindings.join(","))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let keymap: Keymap = "AZERTY, 5=z/Up, 0=".parse().unwrap();
+        assert_eq!(keymap.bindings(0x5).collect::<Vec<_>>(), ["Z", "Up"]);
+        assert_eq!(keymap.bindings(0x4).collect::<Vec<_>>(), ["A"]);
+        assert_eq!(keymap.bindings(0x0).count(), 0);
+        assert_eq!(keymap.to_string().parse(), Ok(keymap));
+
+        let keymap: Keymap = "F=Space".parse().unwrap();
+        assert_eq!(keymap.bindings(0xF).collect::<Vec<_>>(), ["Space"]);
+        assert_eq!(keymap.bindings(0xE).collect::<Vec<_>>(), ["F"]);
+
+        assert!("dvorak,5".parse::<Keymap>().is_err());
+        assert!("10=A".parse::<Keymap>().is_err());
+        assert!("5=Escape".parse::<Keymap>().is_err());
+        assert_eq!(
+            "5=tab".parse::<Keymap>(),
+            Err("Tab is reserved for fast forward".to_string())
+        );
+    }
+
+    #[test]
+    fn test_state() {
+        let keymap: Keymap = "5=W/Up".parse().unwrap();
+        let keys = keymap.state(|key| key == Up);
+        assert!(keys[5]);
+        assert_eq!(keys.iter().filter(|k| **k).count(), 1);
+    }
+
+    #[test]
+    fn test_typed() {
+        assert_eq!(typed('q'), Some(Q));
+        assert_eq!(typed('Q'), Some(Q));
+        assert_eq!(typed('7'), Some(Key7));
+        assert_eq!(typed(';'), Some(Semicolon));
+        assert_eq!(typed(' '), Some(Space));
+        assert_eq!(typed('!'), None);
+    }
+}
@@ -1,15 +1,52 @@
 //!`chip8-base` provides everything you need to get started building your own CHIP-8 interpreter.
 //! See the documentation for the [`Interpreter`][Interpreter] trait to get started.
 
+mod capture;
+mod display;
+mod error;
+mod headless;
+#[cfg(feature = "window")]
 mod interpreter;
+#[cfg(feature = "window")]
+mod keymap;
+mod movie;
+mod palette;
 mod pixel;
 
-pub use interpreter::run;
+pub use capture::{save_png, Recording};
+pub use display::{Display, Resolution, PLANES};
+pub use error::{Error, ErrorKind};
+pub use headless::Headless;
+#[cfg(feature = "window")]
+pub use interpreter::{run, run_with, Frontend, RunOptions};
+#[cfg(feature = "window")]
+pub use keymap::Keymap;
+pub use movie::Movie;
+pub use palette::{Colour, Palette};
 pub use pixel::Pixel;
 
-/// The Interpreter's representation of the CHIP-8 display.
-/// The display is 64x32 pixels, each pixel being either Black or White.
-pub type Display = [[Pixel; 64]; 32];
+/// An XO-CHIP audio pattern, which is played in place of the default tone while the buzzer is active.
+/// The 16 bytes of the buffer are 128 1-bit samples, played most significant bit first and looped.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AudioPattern {
+    pub buffer: [u8; 16],
+    pub pitch: u8,
+}
+
+impl AudioPattern {
+    /// The rate in Hz at which the bits of the pattern are played back.
+    /// A pitch of 64 plays at 4000Hz, and every 48 steps up or down doubles or halves that.
+    pub fn playback_rate(&self) -> f32 {
+        4000.0 * 2_f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+}
+
+/// How many cycles of a given length fit in a 60Hz frame, which is always at least one.
+/// This is how many instructions [`run`][run] and [`Headless`][Headless] run each frame, unless they're told otherwise.
+pub fn cycles_per_frame(speed: std::time::Duration) -> u32 {
+    let frame = std::time::Duration::from_secs(1) / 60;
+    (frame.as_secs_f64() / speed.as_secs_f64()).round().max(1.0) as u32
+}
 
 /// This type is how keyboard input is presented to the Interpreter.
 /// Each of the 16 keys can either be down (`true`) or up (`false`).
@@ -22,14 +59,58 @@ pub trait Interpreter {
     /// This is the main driver function, running the interpreter one clock cycle at a time.
     /// # Return
     /// If the instruction modified the state of the display, then an updated [`Display`][Display] should be returned.
-    /// # Panics
-    /// Should panic if an unrecognised instruction is encountered
-    fn step(&mut self, keys: &Keys) -> Option<Display>;
+    /// # Errors
+    /// Should return an [`Error`][Error] if the instruction could not be executed, such as when an unrecognised instruction is encountered.
+    /// The interpreter is stopped and the error reported when this happens.
+    fn step(&mut self, keys: &Keys) -> Result<Option<Display>, Error>;
 
     /// Returns the duration of a single clock cycle, so the interpreter can keep the time steps uniform.
     /// See [`std::time`][std::time] for more information on [`Duration`][std::time::Duration].
+    /// This also sets how many cycles are run in each 60Hz frame, unless [`RunOptions::instructions_per_frame`] overrides it.
     fn speed(&self) -> std::time::Duration;
 
+    /// Called 60 times a second, to count down the delay and sound timers.
+    /// [`run`][run] calls this from the real time that has passed, so the timers keep the right pace even if the interpreter falls behind,
+    /// and [`Headless`][Headless] calls it at the end of every frame.
+    /// Interpreters that count their timers down in [`step`][Interpreter::step] can leave this as the default, which does nothing.
+    fn timer_tick(&mut self) {}
+
+    /// Indicates that the interpreter can't do anything until the next [`timer_tick`][Interpreter::timer_tick],
+    /// like the COSMAC VIP waiting for the vertical blank before drawing a sprite.
+    /// [`run`][run] skips the rest of the frame when this is `true`, rather than stepping an interpreter that is only waiting.
+    /// The default is `false`.
+    fn waiting_for_vblank(&self) -> bool {
+        false
+    }
+
+    /// Indicates that the interpreter wants to stop, like a debugger being told to quit.
+    /// [`run`][run] stops the interpreter and closes the window as if it had been closed, returning `Ok`.
+    /// The default is `false`.
+    fn quit_requested(&self) -> bool {
+        false
+    }
+
     /// Indicates if the sound buzzer is currently active, such that the interpreter can handle sound accordingly.
     fn buzzer_active(&self) -> bool;
+
+    /// The audio pattern the buzzer should play while active, for XO-CHIP interpreters.
+    /// If this returns `None`, which it does by default, the buzzer plays a plain tone.
+    fn audio_pattern(&self) -> Option<AudioPattern> {
+        None
+    }
+
+    /// Saves the complete state of the interpreter, so it can be restored later with [`load_state`][Interpreter::load_state].
+    /// The format is up to the interpreter, but should be versioned as save states are kept on disk between runs.
+    /// Interpreters that don't support save states can leave this as the default, which returns `None`.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restores a state previously returned by [`save_state`][Interpreter::save_state].
+    /// # Errors
+    /// Should return an error if the state is invalid, leaving the interpreter untouched.
+    /// The default implementation always returns an error.
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        Err("This interpreter does not support save states".into())
+    }
 }
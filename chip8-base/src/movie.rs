@@ -0,0 +1,253 @@
+//movies are a recording of the input to an interpreter, which can be played back to reproduce a run exactly
+//the file is a header, the interpreter's save state from when recording started if it has one,
+//then a record for each cycle the keys changed on. everything is big endian
+//
+//    "C8MV" version:u8 instructions_per_frame:u32 has_state:u8 [len:u32 state] (cycle:u64 keys:u16)*
+//
+//version 1 movies don't have instructions_per_frame, which was added when timers started ticking separately
+//
+//records are written as they happen, so a movie can still be read if the recording was cut off
+
+use crate::Keys;
+#[cfg(feature = "window")]
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"C8MV";
+const VERSION: u8 = 2;
+
+/// A recording of the input to an interpreter, which can be replayed to reproduce a run exactly.
+/// Replays are only exact if the interpreter is deterministic, so any random numbers should come from a seeded generator that is part of its save state.
+///
+/// Movies are recorded and replayed by [`run_with`][crate::run_with], see [`RunOptions::record`][crate::RunOptions::record].
+/// They can also be replayed by a [`Headless`][crate::Headless] runner, to turn a recording into a test.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Movie {
+    /// The interpreter's save state from when recording started, if it supports save states
+    pub state: Option<Vec<u8>>,
+    /// How many cycles were run in each frame, between ticks of the timers, if the movie recorded it
+    pub instructions_per_frame: Option<u32>,
+    /// The cycles the keys changed on, and the state they changed to
+    pub events: Vec<(u64, Keys)>,
+}
+
+impl Movie {
+    /// Reads a movie from a file
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes a movie to a file
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut out)?;
+        out.flush()
+    }
+
+    pub fn from_reader(mut r: impl Read) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut header = [0; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("Not a CHIP-8 movie"));
+        }
+        let instructions_per_frame = match header[4] {
+            1 => None,
+            VERSION => {
+                let mut ipf = [0; 4];
+                r.read_exact(&mut ipf)?;
+                Some(u32::from_be_bytes(ipf)).filter(|n| *n != 0)
+            }
+            _ => return Err(invalid("Unsupported movie version")),
+        };
+        let mut has_state = [0];
+        r.read_exact(&mut has_state)?;
+        let state = match has_state[0] {
+            0 => None,
+            _ => {
+                let mut len = [0; 4];
+                r.read_exact(&mut len)?;
+                //the length isn't trusted to allocate up front, so a corrupt one can't ask for gigabytes
+                let len = u32::from_be_bytes(len) as usize;
+                let mut state = Vec::new();
+                r.by_ref().take(len as u64).read_to_end(&mut state)?;
+                if state.len() != len {
+                    return Err(invalid("Truncated movie"));
+                }
+                Some(state)
+            }
+        };
+
+        //read records until the end, ignoring a partial one if the recording was cut off
+        let mut rest = Vec::new();
+        r.read_to_end(&mut rest)?;
+        let events = rest
+            .chunks_exact(10)
+            .map(|record| {
+                let cycle = u64::from_be_bytes(record[..8].try_into().unwrap());
+                let keys = u16::from_be_bytes([record[8], record[9]]);
+                (cycle, unpack(keys))
+            })
+            .collect();
+        Ok(Movie {
+            state,
+            instructions_per_frame,
+            events,
+        })
+    }
+
+    //movies are always written as the latest version, so one without instructions_per_frame records 0
+    pub fn to_writer(&self, mut w: impl Write) -> io::Result<()> {
+        let ipf = self.instructions_per_frame.unwrap_or(0);
+        write_header(&mut w, ipf, self.state.as_deref())?;
+        for (cycle, keys) in &self.events {
+            write_event(&mut w, *cycle, keys)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_header(w: &mut impl Write, ipf: u32, state: Option<&[u8]>) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    w.write_all(&[VERSION])?;
+    w.write_all(&ipf.to_be_bytes())?;
+    w.write_all(&[state.is_some().into()])?;
+    if let Some(state) = state {
+        w.write_all(&(state.len() as u32).to_be_bytes())?;
+        w.write_all(state)?;
+    }
+    Ok(())
+}
+
+fn write_event(w: &mut impl Write, cycle: u64, keys: &Keys) -> io::Result<()> {
+    w.write_all(&cycle.to_be_bytes())?;
+    w.write_all(&pack(keys).to_be_bytes())
+}
+
+//one bit per key, with key 0 as the least significant
+fn pack(keys: &Keys) -> u16 {
+    keys.iter()
+        .enumerate()
+        .fold(0, |bits, (n, down)| bits | (*down as u16) << n)
+}
+
+fn unpack(bits: u16) -> Keys {
+    std::array::from_fn(|n| bits & (1 << n) != 0)
+}
+
+//records a movie straight to a file as the interpreter runs
+#[cfg(feature = "window")]
+pub(crate) struct Recorder {
+    out: BufWriter<File>,
+    keys: Keys,
+}
+
+#[cfg(feature = "window")]
+impl Recorder {
+    pub fn create(path: &Path, ipf: u32, state: Option<&[u8]>) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write_header(&mut out, ipf, state)?;
+        Ok(Recorder {
+            out,
+            keys: [false; 16],
+        })
+    }
+
+    //note the keys used for a cycle, if they've changed
+    pub fn record(&mut self, cycle: u64, keys: &Keys) -> io::Result<()> {
+        if *keys != self.keys {
+            self.keys = *keys;
+            write_event(&mut self.out, cycle, keys)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+//plays the input from a movie back, cycle by cycle
+#[cfg(feature = "window")]
+pub(crate) struct Player {
+    events: VecDeque<(u64, Keys)>,
+    keys: Keys,
+}
+
+#[cfg(feature = "window")]
+impl Player {
+    pub fn new(movie: &Movie) -> Self {
+        Player {
+            events: movie.events.iter().copied().collect(),
+            keys: [false; 16],
+        }
+    }
+
+    //the keys for a cycle, which stay as they were after the last change
+    pub fn keys(&mut self, cycle: u64) -> Keys {
+        while let Some((_, keys)) = self.events.front().filter(|(c, _)| *c <= cycle) {
+            self.keys = *keys;
+            self.events.pop_front();
+            if self.events.is_empty() {
+                log::info!("Replay finished at cycle {cycle}");
+            }
+        }
+        self.keys
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut keys = [false; 16];
+        keys[0xF] = true;
+        let movie = Movie {
+            state: Some(vec![1, 2, 3]),
+            instructions_per_frame: Some(12),
+            events: vec![(0, keys), (1000, [false; 16])],
+        };
+        let mut bytes = Vec::new();
+        movie.to_writer(&mut bytes).unwrap();
+        assert_eq!(Movie::from_reader(&bytes[..]).unwrap(), movie);
+
+        //a record cut off partway is dropped
+        assert_eq!(
+            Movie::from_reader(&bytes[..bytes.len() - 3])
+                .unwrap()
+                .events,
+            &movie.events[..1]
+        );
+        assert!(Movie::from_reader(&b"not a movie"[..]).is_err());
+
+        //a save state shorter than the header says is an error, even if its length is huge
+        let huge = b"C8MV\x02\0\0\0\x0C\x01\xFF\xFF\xFF\xFF\x01\x02\x03";
+        let e = Movie::from_reader(&huge[..]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "Truncated movie");
+
+        //version 1 movies have no instructions per frame
+        let v1 = b"C8MV\x01\x00\0\0\0\0\0\0\0\x05\x80\x00";
+        let movie = Movie::from_reader(&v1[..]).unwrap();
+        assert_eq!(movie.instructions_per_frame, None);
+        assert_eq!(movie.events.len(), 1);
+        assert!(movie.events[0].1[15]);
+    }
+
+    #[test]
+    #[cfg(feature = "window")]
+    fn test_player() {
+        let mut keys = [false; 16];
+        keys[3] = true;
+        let mut player = Player::new(&Movie {
+            events: vec![(2, keys), (4, [false; 16]), (5, keys)],
+            ..Movie::default()
+        });
+        let held: Vec<bool> = (0..8).map(|cycle| player.keys(cycle)[3]).collect();
+        assert_eq!(held, [false, false, true, true, false, true, true, true]);
+    }
+}
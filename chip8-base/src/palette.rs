@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An RGB colour
+pub type Colour = [u8; 3];
+
+/// The colours the display is drawn in.
+///
+/// There is a colour for each combination of the two XO-CHIP bitplanes, indexed by [`Display::colour`][crate::Display::colour]:
+/// the background where neither is set, the foreground for the first plane, the second plane, and where both planes overlap.
+/// Programs that only draw to the first plane just use the background and foreground.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub colours: [Colour; 4],
+}
+
+impl Palette {
+    /// White on black, with greys for the second plane
+    pub const MONOCHROME: Palette = Palette::new([0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF]);
+
+    /// The default colours of the Octo IDE
+    pub const OCTO: Palette = Palette {
+        colours: [
+            [0x99, 0x66, 0x00],
+            [0xFF, 0xCC, 0x00],
+            [0xFF, 0x66, 0x00],
+            [0x66, 0x22, 0x00],
+        ],
+    };
+
+    /// Dark green on a pale green LCD, like the original Game Boy
+    pub const LCD_GREEN: Palette = Palette {
+        colours: [
+            [0x9B, 0xBC, 0x0F],
+            [0x0F, 0x38, 0x0F],
+            [0x8B, 0xAC, 0x0F],
+            [0x30, 0x62, 0x30],
+        ],
+    };
+
+    /// An amber monochrome monitor
+    pub const AMBER: Palette = Palette {
+        colours: [
+            [0x1F, 0x13, 0x00],
+            [0xFF, 0xB0, 0x00],
+            [0xA6, 0x6A, 0x00],
+            [0xFF, 0xD9, 0x80],
+        ],
+    };
+
+    /// The built in palettes, by name
+    pub const BUILT_IN: [(&'static str, Palette); 4] = [
+        ("monochrome", Palette::MONOCHROME),
+        ("octo", Palette::OCTO),
+        ("lcd green", Palette::LCD_GREEN),
+        ("amber", Palette::AMBER),
+    ];
+
+    /// A two colour palette, with the XO-CHIP plane colours shaded between the background and foreground
+    pub const fn new(background: Colour, foreground: Colour) -> Self {
+        Palette {
+            colours: [
+                background,
+                foreground,
+                mix(background, foreground, 2),
+                mix(background, foreground, 1),
+            ],
+        }
+    }
+
+    pub fn background(&self) -> Colour {
+        self.colours[0]
+    }
+
+    pub fn foreground(&self) -> Colour {
+        self.colours[1]
+    }
+
+    /// The colour for a value returned by [`Display::colour`][crate::Display::colour], with full opacity
+    pub fn rgba(&self, colour: u8) -> [u8; 4] {
+        let [r, g, b] = self.colours[colour as usize & 3];
+        [r, g, b, 0xFF]
+    }
+
+    /// Looks up a built in palette by name, ignoring case and treating `-` and `_` as spaces
+    pub fn named(name: &str) -> Option<Palette> {
+        let name = name.to_lowercase().replace(['-', '_'], " ");
+        Self::BUILT_IN
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, palette)| *palette)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::MONOCHROME
+    }
+}
+
+//thirds of the way from one colour to another
+const fn mix(from: Colour, to: Colour, thirds: u16) -> Colour {
+    let mut colour = [0; 3];
+    let mut i = 0;
+    while i < 3 {
+        colour[i] = ((from[i] as u16 * (3 - thirds) + to[i] as u16 * thirds) / 3) as u8;
+        i += 1;
+    }
+    colour
+}
+
+/// Parses either the name of a built in palette, or a comma separated list of 2 or 4 hex colours like `#000000,#FFFFFF`
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(palette) = Palette::named(s) {
+            return Ok(palette);
+        }
+        let colours = s
+            .split(',')
+            .map(|c| parse_colour(c.trim()))
+            .collect::<Option<Vec<_>>>();
+        match colours.as_deref() {
+            Some(&[background, foreground]) => Ok(Palette::new(background, foreground)),
+            Some(&[a, b, c, d]) => Ok(Palette {
+                colours: [a, b, c, d],
+            }),
+            _ => {
+                let names: Vec<&str> = Palette::BUILT_IN.iter().map(|(n, _)| *n).collect();
+                Err(format!(
+                    "Palettes are one of {}, or 2 or 4 colours like `#000000,#FFFFFF`",
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+fn parse_colour(s: &str) -> Option<Colour> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colours: Vec<String> = self
+            .colours
+            .iter()
+            .map(|[r, g, b]| format!("#{r:02X}{g:02X}{b:02X}"))
+            .collect();
+        write!(f, "{}", colours.join(","))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("LCD-Green".parse(), Ok(Palette::LCD_GREEN));
+        assert_eq!(
+            "#000000, #FFFFFF".parse(),
+            Ok(Palette {
+                colours: [[0; 3], [255; 3], [170; 3], [85; 3]]
+            })
+        );
+        assert_eq!(Palette::OCTO.to_string().parse(), Ok(Palette::OCTO));
+        assert!("#000000".parse::<Palette>().is_err());
+        assert!("#00000G,#FFFFFF".parse::<Palette>().is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//benchmarks for the parts of the interpreter that run for every instruction, so changes that slow them down show up in review
+//run them with `cargo bench -p rs118-chip8`, and criterion compares each run against the one before
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rs118_chip8::interpreter::instruction::{decode, Instruction, Instruction::*};
+use rs118_chip8::interpreter::{Platform, Quirks, VM};
+
+const KEYS: [bool; 16] = [false; 16];
+
+//criterion copies whatever the routine returns through black_box, and displays are big enough to swamp the timings,
+//so only whether one came back is returned
+fn execute(vm: &mut VM, instruction: Instruction) -> bool {
+    vm.execute(black_box(instruction), &KEYS).unwrap().is_some()
+}
+
+//a VM that's run some instructions to set it up, with every extension so all of them can be benchmarked
+fn vm(quirks: Quirks, setup: &[Instruction]) -> VM {
+    let mut vm = VM::new(700, quirks).platform(Platform::Xochip);
+    for instruction in setup {
+        vm.execute(*instruction, &KEYS).unwrap();
+    }
+    vm
+}
+
+fn bench_decode(c: &mut Criterion) {
+    //every opcode there is, valid or not
+    c.bench_function("decode every opcode", |b| {
+        b.iter(|| {
+            (0..=u16::MAX)
+                .filter_map(|op| decode(black_box(op)))
+                .count()
+        })
+    });
+}
+
+fn bench_execute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("execute");
+    //none of these skip or jump, so the pc stays put and they can run forever
+    let instructions = [
+        Setr(0, 0x12),
+        Addr(0, 1),
+        Add(0, 1),
+        Sub(0, 1),
+        Shl(0, 1),
+        Skne(0, 0x12),
+        Rand(0, 0xFF),
+        Bcd(0),
+        Store(15),
+        Load(15),
+    ];
+    for instruction in instructions {
+        //the index points somewhere it can be written to
+        let mut vm = vm(Quirks::SCHIP, &[Setr(0, 0x12), Seti(0x300)]);
+        group.bench_function(instruction.to_string(), |b| {
+            b.iter(|| execute(&mut vm, instruction))
+        });
+    }
+    group.finish();
+}
+
+fn bench_draw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("draw");
+    //sprites from the font, at a position that doesn't line up with a byte
+    let position = [Setr(0, 13), Setr(1, 7), Seti(0x50)];
+    let sprites = [
+        ("lores 8x15", Draw(0, 1, 15), &[][..]),
+        ("hires 16x16", Draw(0, 1, 0), &[Hires][..]),
+        ("xochip 2 planes 8x15", Draw(0, 1, 15), &[Plane(3)][..]),
+    ];
+    for (name, draw, setup) in sprites {
+        let mut vm = vm(Quirks::XOCHIP, &[&position[..], setup].concat());
+        group.bench_function(name, |b| b.iter(|| execute(&mut vm, draw)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode, bench_execute, bench_draw);
+criterion_main!(benches);
@@ -0,0 +1,594 @@
+//an assembler for the syntax the disassembler prints, with a few extras for writing programs by hand
+//
+//    ; comments run to the end of the line
+//    include "sprites.c8asm"  ; assemble another file in place, relative to this one
+//    SPEED = 4                ; constants, which can use numbers and anything defined before them
+//    start:                   ; labels, which can also go before an instruction on the same line
+//        setr v0, SPEED
+//        seti ball
+//        draw v0, v1, 3
+//        jmp start
+//    ball:
+//        sprite ..####..      ; a row of a sprite, 8 or 16 pixels wide
+//        db 0x7E, 0b00111100  ; bytes, which can be negative
+//        dw ball + 1          ; big-endian words
+//
+//numbers are decimal, or hex/binary with a 0x/0b prefix, and operands can add and subtract them
+//mnemonics and operands are the same as the instructions print as, see `Instruction::mnemonic`
+
+mod test;
+
+use crate::disasm::ORIGIN;
+use crate::interpreter::instruction::{decode, Instruction, Instruction::*};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+//how deep includes can nest before we assume they're recursive
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// An error in the source, and where it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file.display(),
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembles source into a ROM to be loaded at [`ORIGIN`].
+/// The path is used for error messages, and includes are relative to it
+pub fn assemble(path: &Path, source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut assembler = Assembler {
+        items: Vec::new(),
+        symbols: HashMap::new(),
+        addr: ORIGIN as usize,
+    };
+    assembler.source(path, source, 0)?;
+    assembler.finish()
+}
+
+//a line in a file, for errors
+#[derive(Debug, Clone)]
+struct Loc {
+    file: Rc<PathBuf>,
+    line: usize,
+}
+
+impl Loc {
+    fn error(&self, column: usize, message: impl Into<String>) -> AsmError {
+        AsmError {
+            file: self.file.to_path_buf(),
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    //identifiers, numbers and sprite rows
+    Word(String),
+    Str(String),
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    column: usize,
+}
+
+//numbers and symbols added or subtracted together
+#[derive(Debug)]
+struct Expr {
+    terms: Vec<(bool, String, usize)>,
+    column: usize,
+}
+
+#[derive(Debug)]
+enum Operand {
+    //a register and its column
+    Register(u8, usize),
+    Value(Expr),
+}
+
+//something that takes up space in the ROM, which can only be turned into bytes once all the labels are known
+#[derive(Debug)]
+enum Data {
+    Instruction {
+        template: Instruction,
+        operands: Vec<Operand>,
+        column: usize,
+    },
+    Bytes(Vec<Expr>),
+    Words(Vec<Expr>),
+    Raw(Vec<u8>),
+}
+
+struct Item {
+    loc: Loc,
+    data: Data,
+}
+
+struct Assembler {
+    items: Vec<Item>,
+    symbols: HashMap<String, i64>,
+    //where the next item goes
+    addr: usize,
+}
+
+impl Assembler {
+    fn source(&mut self, path: &Path, source: &str, depth: usize) -> Result<(), AsmError> {
+        let file = Rc::new(path.to_path_buf());
+        for (n, text) in source.lines().enumerate() {
+            let loc = Loc {
+                file: file.clone(),
+                line: n + 1,
+            };
+            let tokens = lex(text).map_err(|(column, message)| loc.error(column, message))?;
+            self.line(&loc, &tokens, depth)?;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, loc: &Loc, mut tokens: &[Token], depth: usize) -> Result<(), AsmError> {
+        if let [Token {
+            tok: Tok::Word(name),
+            column,
+        }, Token {
+            tok: Tok::Punct(':'),
+            ..
+        }, rest @ ..] = tokens
+        {
+            self.define(loc, *column, name, self.addr as i64)?;
+            tokens = rest;
+        }
+
+        let (word, column, rest) = match tokens {
+            [] => return Ok(()),
+            [Token {
+                tok: Tok::Word(name),
+                column,
+            }, Token {
+                tok: Tok::Punct('='),
+                ..
+            }, rest @ ..] => {
+                let expr = expr(loc, rest, *column)?;
+                let value = self.eval(loc, &expr)?;
+                return self.define(loc, *column, name, value);
+            }
+            [Token {
+                tok: Tok::Word(word),
+                column,
+            }, rest @ ..] => (word.to_lowercase(), *column, rest),
+            [token, ..] => {
+                return Err(loc.error(token.column, "Expected a label, directive or instruction"))
+            }
+        };
+
+        let (data, len) = match word.as_str() {
+            "include" => return self.include(loc, column, rest, depth),
+            "db" => {
+                let bytes = values(loc, rest, column)?;
+                let len = bytes.len();
+                (Data::Bytes(bytes), len)
+            }
+            "dw" => {
+                let words = values(loc, rest, column)?;
+                let len = words.len() * 2;
+                (Data::Words(words), len)
+            }
+            "sprite" => {
+                let row = sprite(loc, rest, column)?;
+                let len = row.len();
+                (Data::Raw(row), len)
+            }
+            mnemonic => {
+                let template = *templates().get(mnemonic).ok_or_else(|| {
+                    loc.error(column, format!("Unknown instruction `{mnemonic}`"))
+                })?;
+                let operands = operands(loc, rest)?;
+                let len = if template == Longi { 4 } else { 2 };
+                let data = Data::Instruction {
+                    template,
+                    operands,
+                    column,
+                };
+                (data, len)
+            }
+        };
+        self.items.push(Item {
+            loc: loc.clone(),
+            data,
+        });
+        self.addr += len;
+        if self.addr > 0x10000 {
+            return Err(loc.error(column, "Program is too big to fit in memory"));
+        }
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        loc: &Loc,
+        column: usize,
+        tokens: &[Token],
+        depth: usize,
+    ) -> Result<(), AsmError> {
+        let [Token {
+            tok: Tok::Str(name),
+            column,
+        }] = tokens
+        else {
+            return Err(loc.error(column, "Expected a file name like `include \"file.c8asm\"`"));
+        };
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(loc.error(
+                *column,
+                "Includes are nested too deeply, are they recursive?",
+            ));
+        }
+        let path = loc.file.parent().unwrap_or(Path::new("")).join(name);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| loc.error(*column, format!("Could not read {}: {e}", path.display())))?;
+        self.source(&path, &source, depth + 1)
+    }
+
+    fn define(&mut self, loc: &Loc, column: usize, name: &str, value: i64) -> Result<(), AsmError> {
+        if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return Err(loc.error(column, format!("Invalid name `{name}`")));
+        }
+        if register(name).is_some() {
+            return Err(loc.error(column, format!("`{name}` is a register")));
+        }
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(loc.error(column, format!("`{name}` is already defined")));
+        }
+        Ok(())
+    }
+
+    fn eval(&self, loc: &Loc, expr: &Expr) -> Result<i64, AsmError> {
+        let mut total: i64 = 0;
+        for (negative, term, column) in &expr.terms {
+            let value = match number(term) {
+                Some(value) => value,
+                None if term.starts_with(|c: char| c.is_ascii_digit()) => {
+                    return Err(loc.error(*column, format!("Invalid number `{term}`")))
+                }
+                None => *self
+                    .symbols
+                    .get(term)
+                    .ok_or_else(|| loc.error(*column, format!("`{term}` is not defined")))?,
+            };
+            let value = if *negative {
+                value.checked_neg()
+            } else {
+                Some(value)
+            };
+            total = value
+                .and_then(|value| total.checked_add(value))
+                .ok_or_else(|| loc.error(*column, "Value out of range"))?;
+        }
+        Ok(total)
+    }
+
+    //evaluate an expression, checking it fits in a field of a given number of bits
+    //bytes can be negative, and wrap around
+    fn value(&self, loc: &Loc, expr: &Expr, bits: u32) -> Result<u16, AsmError> {
+        let value = self.eval(loc, expr)?;
+        let max = (1 << bits) - 1;
+        let min = if bits == 8 { -128 } else { 0 };
+        if value < min || value > max {
+            return Err(loc.error(
+                expr.column,
+                format!("{value:#X} does not fit in {bits} bits"),
+            ));
+        }
+        Ok((value & max) as u16)
+    }
+
+    //turn everything into bytes, now that every label has an address
+    fn finish(self) -> Result<Vec<u8>, AsmError> {
+        let mut rom = Vec::new();
+        for Item { loc, data } in &self.items {
+            match data {
+                Data::Instruction {
+                    template,
+                    operands,
+                    column,
+                } => {
+                    let instruction = self.instruction(loc, *template, operands, *column)?;
+                    rom.extend(instruction.encode().to_be_bytes());
+                    if let [addr] = &operands[..] {
+                        if instruction == Longi {
+                            rom.extend(self.operand(loc, addr, 16)?.to_be_bytes());
+                        }
+                    }
+                }
+                Data::Bytes(bytes) => {
+                    for expr in bytes {
+                        rom.push(self.value(loc, expr, 8)? as u8);
+                    }
+                }
+                Data::Words(words) => {
+                    for expr in words {
+                        rom.extend(self.value(loc, expr, 16)?.to_be_bytes());
+                    }
+                }
+                Data::Raw(bytes) => rom.extend(bytes),
+            }
+        }
+        Ok(rom)
+    }
+
+    fn operand(&self, loc: &Loc, operand: &Operand, bits: u32) -> Result<u16, AsmError> {
+        match operand {
+            Operand::Value(expr) => self.value(loc, expr, bits),
+            Operand::Register(_, column) => {
+                Err(loc.error(*column, "Expected a value, not a register"))
+            }
+        }
+    }
+
+    //fill in the operands of an instruction, which take the same shapes as they print in
+    fn instruction(
+        &self,
+        loc: &Loc,
+        template: Instruction,
+        operands: &[Operand],
+        column: usize,
+    ) -> Result<Instruction, AsmError> {
+        let reg = |operand: &Operand| match operand {
+            Operand::Register(r, _) => Ok(*r),
+            Operand::Value(e) => Err(loc.error(e.column, "Expected a register")),
+        };
+        let nibble = |operand| self.operand(loc, operand, 4).map(|n| n as u8);
+        let byte = |operand| self.operand(loc, operand, 8).map(|n| n as u8);
+        let addr = |operand| self.operand(loc, operand, 12);
+
+        let instruction = match (template, operands) {
+            (Nop | Cls | Ret | Scr | Scl | Exit | Lores | Hires | Audio, []) => template,
+            (Longi, [_]) => Longi,
+            (Scd(_), [n]) => Scd(nibble(n)?),
+            (Scu(_), [n]) => Scu(nibble(n)?),
+            (Plane(_), [n]) => Plane(nibble(n)?),
+            (Jmp(_), [a]) => Jmp(addr(a)?),
+            (Call(_), [a]) => Call(addr(a)?),
+            (Seti(_), [a]) => Seti(addr(a)?),
+            (Jumpi(_), [a]) => Jumpi(addr(a)?),
+            (Ske(..), [x, kk]) => Ske(reg(x)?, byte(kk)?),
+            (Skne(..), [x, kk]) => Skne(reg(x)?, byte(kk)?),
+            (Setr(..), [x, kk]) => Setr(reg(x)?, byte(kk)?),
+            (Addr(..), [x, kk]) => Addr(reg(x)?, byte(kk)?),
+            (Rand(..), [x, kk]) => Rand(reg(x)?, byte(kk)?),
+            (Skre(..), [x, y]) => Skre(reg(x)?, reg(y)?),
+            (Savr(..), [x, y]) => Savr(reg(x)?, reg(y)?),
+            (Loadr(..), [x, y]) => Loadr(reg(x)?, reg(y)?),
+            (Move(..), [x, y]) => Move(reg(x)?, reg(y)?),
+            (Or(..), [x, y]) => Or(reg(x)?, reg(y)?),
+            (And(..), [x, y]) => And(reg(x)?, reg(y)?),
+            (Xor(..), [x, y]) => Xor(reg(x)?, reg(y)?),
+            (Add(..), [x, y]) => Add(reg(x)?, reg(y)?),
+            (Sub(..), [x, y]) => Sub(reg(x)?, reg(y)?),
+            (Shr(..), [x, y]) => Shr(reg(x)?, reg(y)?),
+            (Ssub(..), [x, y]) => Ssub(reg(x)?, reg(y)?),
+            (Shl(..), [x, y]) => Shl(reg(x)?, reg(y)?),
+            (Skrne(..), [x, y]) => Skrne(reg(x)?, reg(y)?),
+            (Draw(..), [x, y, n]) => Draw(reg(x)?, reg(y)?, nibble(n)?),
+            (Skp(_), [x]) => Skp(reg(x)?),
+            (Sknp(_), [x]) => Sknp(reg(x)?),
+            (Moved(_), [x]) => Moved(reg(x)?),
+            (Key(_), [x]) => Key(reg(x)?),
+            (Setrd(_), [x]) => Setrd(reg(x)?),
+            (Setrs(_), [x]) => Setrs(reg(x)?),
+            (Addi(_), [x]) => Addi(reg(x)?),
+            (Ldfnt(_), [x]) => Ldfnt(reg(x)?),
+            (Ldbfnt(_), [x]) => Ldbfnt(reg(x)?),
+            (Pitch(_), [x]) => Pitch(reg(x)?),
+            (Bcd(_), [x]) => Bcd(reg(x)?),
+            (Store(_), [x]) => Store(reg(x)?),
+            (Load(_), [x]) => Load(reg(x)?),
+            (Strpl(_), [x]) => Strpl(reg(x)?),
+            (Ldrpl(_), [x]) => Ldrpl(reg(x)?),
+            _ => {
+                let example = match template {
+                    Longi => "longi 0x1234".to_string(),
+                    _ => template.to_string(),
+                };
+                return Err(loc.error(
+                    column,
+                    format!(
+                        "Wrong operands for `{}`, it looks like `{example}`",
+                        template.mnemonic()
+                    ),
+                ));
+            }
+        };
+        Ok(instruction)
+    }
+}
+
+//an instruction for each mnemonic, found by decoding every opcode so the instruction set only lives in `decode`
+fn templates() -> &'static HashMap<&'static str, Instruction> {
+    static TEMPLATES: OnceLock<HashMap<&'static str, Instruction>> = OnceLock::new();
+    TEMPLATES.get_or_init(|| {
+        let mut templates = HashMap::new();
+        for instruction in (0..=u16::MAX).filter_map(decode) {
+            templates
+                .entry(instruction.mnemonic())
+                .or_insert(instruction);
+        }
+        templates
+    })
+}
+
+fn lex(line: &str) -> Result<Vec<Token>, (usize, String)> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let column = i + 1;
+        let tok = match c {
+            ';' => break,
+            c if c.is_whitespace() => continue,
+            ',' | ':' | '=' | '+' | '-' => Tok::Punct(c),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => s.push(c),
+                        None => return Err((column, "Unterminated string".to_string())),
+                    }
+                }
+                Tok::Str(s)
+            }
+            c if is_word(c) => {
+                let mut s = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+                    s.push(c);
+                }
+                Tok::Word(s)
+            }
+            c => return Err((column, format!("Unexpected character `{c}`"))),
+        };
+        tokens.push(Token { tok, column });
+    }
+    Ok(tokens)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '#')
+}
+
+fn number(s: &str) -> Option<i64> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = s.strip_prefix("0b").or_else(|| s.strip_prefix("0B")) {
+        i64::from_str_radix(bin, 2).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+fn register(s: &str) -> Option<u8> {
+    let r = s.strip_prefix(['v', 'V'])?;
+    match r.len() {
+        1 => u8::from_str_radix(r, 16).ok(),
+        _ => None,
+    }
+}
+
+//split comma separated tokens, none of which can be empty
+fn split<'a>(loc: &Loc, tokens: &'a [Token]) -> Result<Vec<&'a [Token]>, AsmError> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.tok == Tok::Punct(',') {
+            if i == start {
+                return Err(loc.error(token.column, "Missing operand"));
+            }
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    match &tokens[start..] {
+        [] if start > 0 => return Err(loc.error(tokens[start - 1].column, "Missing operand")),
+        [] => (),
+        rest => parts.push(rest),
+    }
+    Ok(parts)
+}
+
+fn operands(loc: &Loc, tokens: &[Token]) -> Result<Vec<Operand>, AsmError> {
+    split(loc, tokens)?
+        .into_iter()
+        .map(|part| match part {
+            [Token {
+                tok: Tok::Word(w),
+                column,
+            }] if register(w).is_some() => Ok(Operand::Register(register(w).unwrap(), *column)),
+            _ => expr(loc, part, part[0].column).map(Operand::Value),
+        })
+        .collect()
+}
+
+fn values(loc: &Loc, tokens: &[Token], column: usize) -> Result<Vec<Expr>, AsmError> {
+    let parts = split(loc, tokens)?;
+    if parts.is_empty() {
+        return Err(loc.error(column, "Expected at least one value"));
+    }
+    parts
+        .into_iter()
+        .map(|part| expr(loc, part, part[0].column))
+        .collect()
+}
+
+fn expr(loc: &Loc, tokens: &[Token], column: usize) -> Result<Expr, AsmError> {
+    let mut terms = Vec::new();
+    let mut negative = false;
+    //whether the next token should be a term rather than an operator
+    let mut want_term = true;
+    for token in tokens {
+        match (&token.tok, want_term) {
+            (Tok::Punct('-'), true) if terms.is_empty() => negative = true,
+            (Tok::Punct('+'), true) if terms.is_empty() => (),
+            (Tok::Word(w), true) => {
+                terms.push((negative, w.clone(), token.column));
+                want_term = false;
+            }
+            (Tok::Punct(op @ ('+' | '-')), false) => {
+                negative = *op == '-';
+                want_term = true;
+            }
+            _ => return Err(loc.error(token.column, "Expected a number or name")),
+        }
+    }
+    if want_term {
+        let column = tokens.last().map_or(column, |t| t.column);
+        return Err(loc.error(column, "Expected a number or name"));
+    }
+    Ok(Expr { terms, column })
+}
+
+//a row of pixels, with `#` for on and `.` for off
+fn sprite(loc: &Loc, tokens: &[Token], column: usize) -> Result<Vec<u8>, AsmError> {
+    let [Token {
+        tok: Tok::Word(row),
+        column,
+    }] = tokens
+    else {
+        return Err(loc.error(column, "Expected a row of pixels like `sprite ..####..`"));
+    };
+    if row.len() != 8 && row.len() != 16 || row.contains(|c| c != '#' && c != '.') {
+        return Err(loc.error(
+            *column,
+            "Sprite rows are 8 or 16 pixels of `#` for on and `.` for off",
+        ));
+    }
+    let bits = row
+        .chars()
+        .fold(0u16, |bits, c| bits << 1 | (c == '#') as u16);
+    Ok(match row.len() {
+        8 => vec![bits as u8],
+        _ => bits.to_be_bytes().to_vec(),
+    })
+}
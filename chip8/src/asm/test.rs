@@ -0,0 +1,135 @@
+#![cfg(test)]
+use super::*;
+use crate::disasm::disassemble;
+
+fn asm(source: &str) -> Result<Vec<u8>, AsmError> {
+    assemble(Path::new("test.c8asm"), source)
+}
+
+//the line, column and message of an error
+fn error(source: &str) -> (usize, usize, String) {
+    let e = asm(source).unwrap_err();
+    (e.line, e.column, e.message)
+}
+
+// test that every bundled ROM comes back byte for byte after disassembling and assembling it
+#[test]
+fn test_round_trip() {
+    let roms = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../roms")).unwrap();
+    for entry in roms {
+        let path = entry.unwrap().path();
+        //the conformance suite's golden images are in there too
+        if path.is_dir() {
+            continue;
+        }
+        let rom = std::fs::read(&path).unwrap();
+        let source = disassemble(&rom).unwrap();
+        let assembled = assemble(&path, &source).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(assembled, rom, "{}", path.display());
+    }
+}
+
+// test labels, constants, expressions and data directives
+#[test]
+fn test_assemble() {
+    let source = "
+        X = 0x10
+        Y = X - 2
+    start:
+        setr v0, X
+        setr vF, -1         ; wraps to 0xFF
+        draw v0, v1, 2
+        longi sprite + 1
+        jmp start
+    sprite: db 0b00111100, 255
+        sprite #..............#
+        dw start, Y
+    ";
+    assert_eq!(
+        asm(source).unwrap(),
+        [
+            0x60, 0x10, //setr
+            0x6F, 0xFF, //setr
+            0xD0, 0x12, //draw
+            0xF0, 0x00, 0x02, 0x0D, //longi
+            0x12, 0x00, //jmp
+            0x3C, 0xFF, //db
+            0x80, 0x01, //sprite
+            0x02, 0x00, 0x00, 0x0E, //dw
+        ]
+    );
+}
+
+// test that includes are assembled in place, relative to the including file
+#[test]
+fn test_include() {
+    let dir = std::env::temp_dir().join(format!("chip8-asm-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(dir.join("lib/sprites.c8asm"), "ball: sprite ..##....\n").unwrap();
+    std::fs::write(dir.join("loop.c8asm"), "include \"loop.c8asm\"\n").unwrap();
+
+    let main = dir.join("main.c8asm");
+    let rom = assemble(&main, "seti ball\ninclude \"lib/sprites.c8asm\"").unwrap();
+    assert_eq!(rom, [0xA2, 0x02, 0x30]);
+
+    let e = assemble(&main, "include \"loop.c8asm\"").unwrap_err();
+    assert!(e.message.contains("nested too deeply"));
+    let e = assemble(&main, "  include \"missing.c8asm\"").unwrap_err();
+    assert_eq!((e.line, e.column), (1, 11));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// test that errors point at the right line and column
+#[test]
+fn test_errors() {
+    assert_eq!(
+        error("cls\n  frob v0"),
+        (2, 3, "Unknown instruction `frob`".to_string())
+    );
+    assert_eq!(
+        error("setr v0, 0x100"),
+        (1, 10, "0x100 does not fit in 8 bits".to_string())
+    );
+    assert_eq!(
+        error("jmp nowhere"),
+        (1, 5, "`nowhere` is not defined".to_string())
+    );
+    assert_eq!(
+        error("setr v0"),
+        (
+            1,
+            1,
+            "Wrong operands for `setr`, it looks like `setr v0, 0x00`".to_string()
+        )
+    );
+    assert_eq!(
+        error("move v0, 3"),
+        (1, 10, "Expected a register".to_string())
+    );
+    assert_eq!(
+        error("jmp v0"),
+        (1, 5, "Expected a value, not a register".to_string())
+    );
+    assert_eq!(error("db 1,, 2"), (1, 6, "Missing operand".to_string()));
+    assert_eq!(
+        error("jmp 0x7FFFFFFFFFFFFFFF + 1"),
+        (1, 26, "Value out of range".to_string())
+    );
+    assert_eq!(
+        error("a: cls\na: cls"),
+        (2, 1, "`a` is already defined".to_string())
+    );
+    assert_eq!(
+        error("sprite ..#"),
+        (
+            1,
+            8,
+            "Sprite rows are 8 or 16 pixels of `#` for on and `.` for off".to_string()
+        )
+    );
+    assert_eq!(
+        error("db 0x1G"),
+        (1, 4, "Invalid number `0x1G`".to_string())
+    );
+}
@@ -0,0 +1,110 @@
+//runs a ROM as fast as it will go, with no frontend and no sleeping, to measure how fast the interpreter is on its own
+//the keys are never pressed, so ROMs that wait for input won't get far, but demos and most games' idle loops still run
+
+mod test;
+
+use crate::interpreter::{instruction::decode, VM};
+use chip8_base::{Error, Interpreter};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+//the widest bar in the histogram
+const BAR_WIDTH: usize = 40;
+
+/// How a benchmark run went
+#[derive(Debug)]
+pub struct Report {
+    pub instructions: u64,
+    pub elapsed: Duration,
+    /// How many times each instruction ran, by mnemonic, most common first
+    pub histogram: Vec<(&'static str, u64)>,
+    /// Why the run ended before running all its cycles, if it did
+    pub stopped: Option<Stopped>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Stopped {
+    Exited,
+    Error(Error),
+}
+
+/// Runs the VM for a number of cycles, or until it exits or fails, ticking the timers every `ipf` cycles,
+/// or every frame's worth of cycles at the VM's speed if it isn't given.
+/// Like the frontends, a frame ends early once the VM is waiting for the next one, so those cycles aren't counted.
+pub fn run(mut vm: VM, cycles: u64, ipf: Option<u32>) -> Report {
+    let ipf = ipf.unwrap_or_else(|| chip8_base::cycles_per_frame(vm.speed()));
+    let keys = [false; 16];
+    //counting by opcode is only a memory read, so it's sorted into instructions after the clock stops
+    let mut counts = vec![0_u64; 0x10000];
+    let (mut instructions, mut frame) = (0, 0);
+    let mut stopped = None;
+    let start = Instant::now();
+    while instructions < cycles {
+        if frame == ipf || vm.waiting_for_vblank() {
+            vm.timer_tick();
+            frame = 0;
+        }
+        if vm.halted() {
+            stopped = Some(Stopped::Exited);
+            break;
+        }
+        let opcode = vm.read_opcode(vm.pc());
+        if let Err(e) = vm.step(&keys) {
+            stopped = Some(Stopped::Error(e));
+            break;
+        }
+        instructions += 1;
+        frame += 1;
+        if let Some(opcode) = opcode {
+            counts[opcode as usize] += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let mut histogram = BTreeMap::new();
+    for (opcode, count) in counts.into_iter().enumerate().filter(|(_, n)| *n != 0) {
+        //everything that ran decoded, or it would have stopped the run
+        let name = decode(opcode as u16).map_or("invalid", |i| i.mnemonic());
+        *histogram.entry(name).or_default() += count;
+    }
+    let mut histogram: Vec<_> = histogram.into_iter().collect();
+    histogram.sort_by_key(|(_, count)| Reverse(*count));
+    Report {
+        instructions,
+        elapsed,
+        histogram,
+        stopped,
+    }
+}
+
+impl Report {
+    pub fn per_second(&self) -> f64 {
+        self.instructions as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Ran {} instructions in {:.3?}, {:.0} a second",
+            self.instructions,
+            self.elapsed,
+            self.per_second()
+        )?;
+        match &self.stopped {
+            Some(Stopped::Exited) => writeln!(f, "Stopped early as the ROM exited")?,
+            Some(Stopped::Error(e)) => writeln!(f, "Stopped early: {e}")?,
+            None => (),
+        }
+        let max = self.histogram.first().map_or(1, |(_, n)| *n);
+        for (name, count) in &self.histogram {
+            let share = *count as f64 / self.instructions as f64 * 100.0;
+            let bar = "#".repeat((count * BAR_WIDTH as u64).div_ceil(max) as usize);
+            writeln!(f, "{name:<7}{count:>12}{share:>7.2}% {bar}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg(test)]
+use super::*;
+use crate::interpreter::{Platform, Quirks};
+use chip8_base::ErrorKind;
+
+fn vm(program: &[u8]) -> VM {
+    VM::new(700, Quirks::default())
+        .platform(Platform::Schip)
+        .load(program)
+        .unwrap()
+}
+
+// test that the histogram counts every instruction that ran
+#[test]
+fn test_histogram() {
+    //add, add, jump back
+    let report = run(vm(&[0x70, 0x01, 0x71, 0x01, 0x12, 0x00]), 3000, Some(10));
+    assert_eq!(report.instructions, 3000);
+    assert_eq!(report.histogram, [("addr", 2000), ("jmp", 1000)]);
+    assert_eq!(report.stopped, None);
+    let text = report.to_string();
+    assert!(text.starts_with("Ran 3000 instructions in "));
+    assert!(text.contains("addr           2000  66.67% ########################################\n"));
+    assert!(text.contains("jmp            1000  33.33% ####################\n"));
+}
+
+// test that runs stop when the ROM exits or fails
+#[test]
+fn test_stopped() {
+    let report = run(vm(&[0x60, 0x01, 0x00, 0xFD]), 100, None);
+    assert_eq!(report.instructions, 2);
+    assert_eq!(report.stopped, Some(Stopped::Exited));
+
+    let report = run(vm(&[0x60, 0x01, 0xE0, 0x00]), 100, None);
+    assert_eq!(report.instructions, 1);
+    assert_eq!(report.histogram, [("setr", 1)]);
+    assert!(matches!(
+        report.stopped,
+        Some(Stopped::Error(Error {
+            kind: ErrorKind::InvalidOpcode,
+            pc: 0x202,
+            ..
+        }))
+    ));
+}
@@ -0,0 +1,59 @@
+//settings that can be kept in a TOML file instead of being passed on the command line every time
+//anything given on the command line overrides the config file
+//
+//    palette = "lcd green"
+//    keymap = "azerty,5=Z/Up,8=S/Down"
+
+mod test;
+
+use serde::{de, Deserialize, Deserializer};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(deserialize_with = "parse")]
+    pub palette: Option<chip8_base::Palette>,
+    #[serde(deserialize_with = "parse")]
+    pub keymap: Option<chip8_base::Keymap>,
+}
+
+impl Config {
+    //read the config from a file if one was given, or from the default location if there's a file there
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match default_path().filter(|p| p.is_file()) {
+                Some(path) => path,
+                None => return Ok(Config::default()),
+            },
+        };
+        log::info!("Reading config from {}", path.display());
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read config {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("Invalid config {}: {e}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+}
+
+//~/.config/rs118-chip8/config.toml on Linux, or wherever configs go on other platforms
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rs118-chip8").join("config.toml"))
+}
+
+//settings are written the same way as on the command line, so share the same parsing
+pub fn parse<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Option::<String>::deserialize(d)?
+        .map(|s| s.parse().map_err(de::Error::custom))
+        .transpose()
+}
@@ -0,0 +1,30 @@
+#![cfg(test)]
+
+use super::*;
+use chip8_base::{Keymap, Palette};
+
+#[test]
+fn test_parse() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    assert_eq!(
+        Config::parse("palette = \"amber\"").unwrap().palette,
+        Some(Palette::AMBER)
+    );
+    assert_eq!(
+        Config::parse("palette = \"#000000,#00FF00\"")
+            .unwrap()
+            .palette,
+        Some(Palette::new([0, 0, 0], [0, 255, 0]))
+    );
+
+    let config = Config::parse("keymap = \"dvorak,5=Up\"\npalette = \"octo\"").unwrap();
+    assert_eq!(
+        config.keymap,
+        Some("dvorak,5=Up".parse::<Keymap>().unwrap())
+    );
+    assert_eq!(config.palette, Some(Palette::OCTO));
+
+    let error = Config::parse("palette = \"plaid\"").unwrap_err();
+    assert!(error.to_string().contains("Palettes are one of"));
+    assert!(Config::parse("colour = \"amber\"").is_err());
+}
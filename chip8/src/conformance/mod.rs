@@ -0,0 +1,174 @@
+//the conformance suite: test ROMs run headlessly for a fixed number of frames, and the display compared with a golden image
+//golden images are PNGs in a golden directory next to the ROMs, saved by `chip8 test --bless`, which should be checked by eye
+//both `chip8 test` and the integration tests run the suite from here, so they can't disagree
+//ROMs that are known to fail are still run, but never blessed, so a failure screen can't pass as a golden image
+
+mod test;
+
+use crate::interpreter::{Platform, VM};
+use chip8_base::{Display, Headless, Palette, Resolution};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A test ROM in the suite, and how to run it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Case {
+    /// The ROM's file name, in the ROM directory
+    pub rom: &'static str,
+    pub platform: Platform,
+    /// How many frames to run before comparing the display, which is long enough for every test to finish
+    pub frames: u64,
+    /// Why the ROM fails, if it's known to. These are run but have no golden image
+    pub known_failure: Option<&'static str>,
+}
+
+/// The test ROMs in `roms/`
+pub const SUITE: [Case; 3] = [
+    Case {
+        rom: "test_opcode.ch8",
+        platform: Platform::Vip,
+        frames: 120,
+        known_failure: None,
+    },
+    Case {
+        rom: "SCTEST.CH8",
+        platform: Platform::Schip,
+        frames: 120,
+        known_failure: Some(
+            "error 24, Fx1E setting vF when the index goes past 0xFFF, which only the Amiga interpreter did",
+        ),
+    },
+    Case {
+        rom: "chip8-test-rom-with-audio.ch8",
+        platform: Platform::Vip,
+        frames: 120,
+        known_failure: None,
+    },
+];
+
+/// What happened when a case was checked against its golden image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    /// This many pixels were different from the golden image, at high resolution
+    Mismatch(usize),
+    /// There's no golden image to compare with
+    NoGolden,
+    /// The ROM ran, but is known to fail for this reason
+    KnownFailure(&'static str),
+    /// The ROM couldn't be loaded or stopped with an error
+    Error(String),
+}
+
+impl Outcome {
+    /// Whether this is what the suite expects, which is a pass or a known failure
+    pub fn expected(&self) -> bool {
+        matches!(self, Outcome::Pass | Outcome::KnownFailure(_))
+    }
+}
+
+impl Case {
+    /// Where the golden image is, for ROMs in `roms`
+    pub fn golden(&self, roms: &Path) -> PathBuf {
+        roms.join("golden")
+            .join(Path::new(self.rom).with_extension("png"))
+    }
+
+    /// Runs the ROM from `roms` for the case's frames, with no keys pressed, and returns the display at the end
+    pub fn run(&self, roms: &Path) -> Result<Display, String> {
+        let path = roms.join(self.rom);
+        let bytes =
+            std::fs::read(&path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+        let vm = VM::new(700, self.platform.quirks())
+            .platform(self.platform)
+            .load(&bytes)
+            .map_err(|e| e.to_string())?;
+        let mut runner = Headless::new(vm);
+        runner.run_frames(self.frames).map_err(|e| e.to_string())?;
+        Ok(*runner.display())
+    }
+
+    /// Runs the ROM and compares the display with the golden image
+    pub fn check(&self, roms: &Path) -> Outcome {
+        let display = match self.run(roms) {
+            Ok(display) => display,
+            Err(e) => return Outcome::Error(e),
+        };
+        if let Some(reason) = self.known_failure {
+            return Outcome::KnownFailure(reason);
+        }
+        let golden = match read_golden(&self.golden(roms)) {
+            Ok(Some(golden)) => golden,
+            Ok(None) => return Outcome::NoGolden,
+            Err(e) => return Outcome::Error(e),
+        };
+        match pixels(&display)
+            .iter()
+            .zip(&golden)
+            .filter(|(a, b)| a != b)
+            .count()
+        {
+            0 => Outcome::Pass,
+            n => Outcome::Mismatch(n),
+        }
+    }
+
+    /// Runs the ROM and saves the display as its golden image, unless it's known to fail
+    pub fn bless(&self, roms: &Path) -> Result<PathBuf, String> {
+        if let Some(reason) = self.known_failure {
+            return Err(format!("known failure, not saving what it draws: {reason}"));
+        }
+        let display = self.run(roms)?;
+        let path = self.golden(roms);
+        let dir = path.parent().expect("golden images are in a directory");
+        std::fs::create_dir_all(dir)
+            .and_then(|_| chip8_base::save_png(&display, &Palette::MONOCHROME, 1, &path))
+            .map_err(|e| format!("Could not write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Mismatch(n) => write!(f, "FAIL, {n} pixels differ from the golden image"),
+            Outcome::NoGolden => write!(f, "no golden image, bless one to compare with"),
+            Outcome::KnownFailure(reason) => write!(f, "known failure, {reason}"),
+            Outcome::Error(e) => write!(f, "FAIL, {e}"),
+        }
+    }
+}
+
+//the colour of every pixel at high resolution, with low resolution pixels doubled, as save_png draws them at scale 1
+fn pixels(display: &Display) -> Vec<u8> {
+    let (width, height) = (Resolution::High.width(), Resolution::High.height());
+    let scale = width / display.width();
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| display.colour(x / scale, y / scale)))
+        .collect()
+}
+
+//the palette index of every pixel in a golden image, or None if there isn't one
+fn read_golden(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+    };
+    let error = |e: png::DecodingError| format!("Could not read {}: {e}", path.display());
+    let mut reader = png::Decoder::new(file).read_info().map_err(error)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(error)?;
+    let size = (Resolution::High.width(), Resolution::High.height());
+    if (info.width as usize, info.height as usize) != size
+        || info.color_type != png::ColorType::Indexed
+    {
+        return Err(format!(
+            "{} isn't a golden image, which are 128x64 and indexed",
+            path.display()
+        ));
+    }
+    pixels.truncate(size.0 * size.1);
+    Ok(Some(pixels))
+}
@@ -0,0 +1,52 @@
+#![cfg(test)]
+use super::*;
+
+// test that a blessed golden image passes, and that drawing anything else fails
+#[test]
+fn test_golden() {
+    let roms = std::env::temp_dir().join(format!("chip8-conformance-{}", std::process::id()));
+    std::fs::create_dir_all(&roms).unwrap();
+    let case = Case {
+        rom: "zero.ch8",
+        platform: Platform::Vip,
+        frames: 2,
+        known_failure: None,
+    };
+    assert!(matches!(case.check(&roms), Outcome::Error(_)));
+
+    //draw a 0 at the top left, then loop forever
+    let rom = |x: u8| [0x60, x, 0xA0, 0x50, 0xD0, 0x15, 0x12, 0x06];
+    std::fs::write(roms.join(case.rom), rom(0)).unwrap();
+    assert_eq!(case.check(&roms), Outcome::NoGolden);
+    assert_eq!(case.bless(&roms), Ok(roms.join("golden/zero.png")));
+    assert_eq!(case.check(&roms), Outcome::Pass);
+
+    //moving it along a pixel changes the pixels at each end of its rows, which are 2x2 at high resolution
+    std::fs::write(roms.join(case.rom), rom(1)).unwrap();
+    assert_eq!(case.check(&roms), Outcome::Mismatch(64));
+
+    //known failures are run, but never compared or blessed
+    let known = Case {
+        known_failure: Some("draws in the wrong place"),
+        ..case
+    };
+    assert_eq!(
+        known.check(&roms),
+        Outcome::KnownFailure("draws in the wrong place")
+    );
+    assert!(known.bless(&roms).is_err());
+    std::fs::remove_file(roms.join(case.rom)).unwrap();
+    assert!(matches!(known.check(&roms), Outcome::Error(_)));
+    std::fs::remove_dir_all(&roms).unwrap();
+}
+
+// test that low resolution pixels are doubled up to compare at high resolution
+#[test]
+fn test_pixels() {
+    let mut display = Display::default();
+    display[1][2] = chip8_base::Pixel::White;
+    let pixels = pixels(&display);
+    assert_eq!(pixels.len(), 128 * 64);
+    assert_eq!(pixels.iter().filter(|p| **p == 1).count(), 4);
+    assert_eq!(pixels[3 * 128 + 5], 1);
+}
@@ -0,0 +1,199 @@
+//parsing for the commands typed at the debugger prompt
+//addresses are always hex, with or without a 0x prefix, and values are decimal unless prefixed with 0x
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Continue,
+    Pause,
+    Step(u32),
+    Next,
+    Finish,
+    Break(Breakpoint),
+    Delete(usize),
+    List,
+    Registers,
+    Examine(u16, u16),
+    Help,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    //stop when the pc reaches an address, if the condition holds, or anywhere the condition holds
+    At {
+        addr: Option<u16>,
+        condition: Option<Condition>,
+    },
+    //stop when the byte at an address is written to
+    Watch(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub operand: Operand,
+    pub op: Op,
+    pub value: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(u8),
+    Index,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Condition {
+    pub fn holds(&self, lhs: u16) -> bool {
+        match self.op {
+            Op::Eq => lhs == self.value,
+            Op::Ne => lhs != self.value,
+            Op::Lt => lhs < self.value,
+            Op::Le => lhs <= self.value,
+            Op::Gt => lhs > self.value,
+            Op::Ge => lhs >= self.value,
+        }
+    }
+}
+
+pub const HELP: &str = "\
+Commands:
+  c, continue              resume execution
+  p, pause                 pause execution
+  s, step [n]              execute n instructions (default 1)
+  n, next                  step, running over subroutine calls
+  f, finish                run until the current subroutine returns
+  b, break <addr>          break when the pc reaches addr
+  b, break [addr] if <cond> break when cond holds (at addr), e.g. `b 2a0 if v3 == 0x10`
+  w, watch <addr>          break when the byte at addr is written
+  d, delete <n>            delete breakpoint n
+  l, list                  list breakpoints
+  r, regs                  show registers, timers and the stack
+  x <addr> [len]           examine len bytes of memory (default 16)
+  h, help                  show this message
+  q, quit                  exit
+Addresses are hex, values are decimal unless prefixed with 0x.";
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (cmd, args) = match words.split_first() {
+        Some((cmd, args)) => (*cmd, args),
+        None => return Err("No command given".to_string()),
+    };
+    let command = match (cmd, args) {
+        ("c" | "continue", []) => Command::Continue,
+        ("p" | "pause", []) => Command::Pause,
+        ("s" | "step", []) => Command::Step(1),
+        ("s" | "step", [n]) => Command::Step(value(n)?.into()),
+        ("n" | "next", []) => Command::Next,
+        ("f" | "finish", []) => Command::Finish,
+        ("b" | "break", ["if", cond @ ..]) => Command::Break(Breakpoint::At {
+            addr: None,
+            condition: Some(condition(cond)?),
+        }),
+        ("b" | "break", [addr]) => Command::Break(Breakpoint::At {
+            addr: Some(address(addr)?),
+            condition: None,
+        }),
+        ("b" | "break", [addr, "if", cond @ ..]) => Command::Break(Breakpoint::At {
+            addr: Some(address(addr)?),
+            condition: Some(condition(cond)?),
+        }),
+        ("w" | "watch", [addr]) => Command::Break(Breakpoint::Watch(address(addr)?)),
+        ("d" | "delete", [n]) => {
+            Command::Delete(n.parse().map_err(|_| format!("Invalid breakpoint: {n}"))?)
+        }
+        ("l" | "list", []) => Command::List,
+        ("r" | "regs", []) => Command::Registers,
+        ("x", [addr]) => Command::Examine(address(addr)?, 16),
+        ("x", [addr, len]) => Command::Examine(address(addr)?, value(len)?),
+        ("h" | "help", []) => Command::Help,
+        ("q" | "quit", []) => Command::Quit,
+        _ => return Err(format!("Invalid command: {line}, type `help` for help")),
+    };
+    Ok(command)
+}
+
+fn address(s: &str) -> Result<u16, String> {
+    let digits = s.trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid address: {s}"))
+}
+
+fn value(s: &str) -> Result<u16, String> {
+    match s.strip_prefix("0x") {
+        Some(digits) => u16::from_str_radix(digits, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("Invalid value: {s}"))
+}
+
+fn condition(words: &[&str]) -> Result<Condition, String> {
+    let [lhs, op, rhs] = words else {
+        return Err("Conditions look like `v3 == 10`".to_string());
+    };
+    let operand = match lhs.to_lowercase().as_str() {
+        "i" => Operand::Index,
+        reg => reg
+            .strip_prefix('v')
+            .and_then(|r| u8::from_str_radix(r, 16).ok())
+            .filter(|r| *r < 16)
+            .map(Operand::Register)
+            .ok_or_else(|| format!("Invalid register: {lhs}"))?,
+    };
+    let op = match *op {
+        "==" => Op::Eq,
+        "!=" => Op::Ne,
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        ">" => Op::Gt,
+        ">=" => Op::Ge,
+        _ => return Err(format!("Invalid comparison: {op}")),
+    };
+    Ok(Condition {
+        operand,
+        op,
+        value: value(rhs)?,
+    })
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::At { addr, condition } => {
+                if let Some(addr) = addr {
+                    write!(f, "at {addr:#06X}")?;
+                }
+                if let Some(c) = condition {
+                    let lhs = match c.operand {
+                        Operand::Register(r) => format!("v{r:X}"),
+                        Operand::Index => "i".to_string(),
+                    };
+                    let op = match c.op {
+                        Op::Eq => "==",
+                        Op::Ne => "!=",
+                        Op::Lt => "<",
+                        Op::Le => "<=",
+                        Op::Gt => ">",
+                        Op::Ge => ">=",
+                    };
+                    if addr.is_some() {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "if {lhs} {op} {:#X}", c.value)?;
+                }
+                Ok(())
+            }
+            Breakpoint::Watch(addr) => write!(f, "watch {addr:#06X}"),
+        }
+    }
+}
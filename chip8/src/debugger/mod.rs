@@ -0,0 +1,321 @@
+mod command;
+mod test;
+
+use crate::interpreter::{
+    instruction::{decode, Instruction},
+    VM,
+};
+use chip8_base::{AudioPattern, Display, Error, Interpreter, Keys};
+use command::{Breakpoint, Command, Operand};
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+//what the debugger is doing between commands
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    Running,
+    Paused,
+    Stepping(u32),
+    //run until the pc gets to an address with the stack at a given depth, to step over a call
+    RunTo { addr: u16, depth: usize },
+    //run until the stack is shallower than a given depth, when the current subroutine returns
+    Finish(usize),
+}
+
+//a breakpoint, and whether its condition held on the last step so conditions only break when they become true
+struct Entry {
+    breakpoint: Breakpoint,
+    held: bool,
+}
+
+/// Wraps the VM to pause, step and break on it, driven by commands typed on stdin.
+/// The window keeps running while paused, it just doesn't get any new frames.
+pub struct Debugger {
+    vm: VM,
+    commands: Receiver<Command>,
+    mode: Mode,
+    breakpoints: Vec<Option<Entry>>,
+    //the pc we last paused at, so resuming from a breakpoint doesn't hit it again straight away
+    paused_at: Option<u16>,
+    //an error from the VM, which is held on to while paused so the state can be inspected
+    fault: Option<Error>,
+    //set by the quit command, so the frontend shuts down and saves everything on the way out
+    quit: bool,
+}
+
+impl Debugger {
+    /// Starts the debugger paused, with a thread reading commands from stdin
+    pub fn new(vm: VM) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("Debugger prompt".to_string())
+            .spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    let Ok(line) = line else { break };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match command::parse(&line) {
+                        Ok(command) => {
+                            if tx.send(command).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => println!("{e}"),
+                    }
+                }
+            })
+            .expect("Could not start debugger prompt thread");
+
+        println!("{}", command::HELP);
+        let debugger = Debugger::with_commands(vm, rx);
+        debugger.show_current();
+        debugger
+    }
+
+    fn with_commands(vm: VM, commands: Receiver<Command>) -> Self {
+        let paused_at = Some(vm.pc());
+        Debugger {
+            vm,
+            commands,
+            mode: Mode::Paused,
+            breakpoints: Vec::new(),
+            paused_at,
+            fault: None,
+            quit: false,
+        }
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Continue => self.mode = Mode::Running,
+            Command::Pause => {
+                self.pause();
+                self.show_current();
+            }
+            Command::Step(n) => self.mode = Mode::Stepping(n),
+            Command::Next => {
+                let pc = self.vm.pc();
+                self.mode = match self.vm.read_opcode(pc).and_then(decode) {
+                    Some(Instruction::Call(_)) => Mode::RunTo {
+                        addr: pc + 2,
+                        depth: self.vm.stack().len(),
+                    },
+                    _ => Mode::Stepping(1),
+                };
+            }
+            Command::Finish => match self.vm.stack().len() {
+                0 => println!("Not in a subroutine"),
+                depth => self.mode = Mode::Finish(depth),
+            },
+            Command::Break(breakpoint) => {
+                println!("Breakpoint {} {breakpoint}", self.breakpoints.len());
+                let held = self.condition_holds(&breakpoint);
+                self.breakpoints.push(Some(Entry { breakpoint, held }));
+            }
+            Command::Delete(n) => match self.breakpoints.get_mut(n).and_then(Option::take) {
+                Some(_) => println!("Deleted breakpoint {n}"),
+                None => println!("No breakpoint {n}"),
+            },
+            Command::List => {
+                for (n, entry) in self.breakpoints.iter().enumerate() {
+                    if let Some(entry) = entry {
+                        println!("{n}: {}", entry.breakpoint);
+                    }
+                }
+            }
+            Command::Registers => self.show_registers(),
+            Command::Examine(addr, len) => self.show_memory(addr, len),
+            Command::Help => println!("{}", command::HELP),
+            Command::Quit => self.quit = true,
+        }
+    }
+
+    fn pause(&mut self) {
+        self.mode = Mode::Paused;
+        self.paused_at = Some(self.vm.pc());
+    }
+
+    //whether a breakpoint's condition holds right now, ignoring its address
+    fn condition_holds(&self, breakpoint: &Breakpoint) -> bool {
+        match breakpoint {
+            Breakpoint::At {
+                condition: Some(c), ..
+            } => c.holds(match c.operand {
+                Operand::Register(r) => self.vm.registers()[r as usize].into(),
+                Operand::Index => self.vm.index(),
+            }),
+            _ => true,
+        }
+    }
+
+    //check the breakpoints before executing the instruction at the pc, returning the first one hit
+    fn check_breakpoints(&mut self) -> Option<usize> {
+        let pc = self.vm.pc();
+        let mut hit = None;
+        for n in 0..self.breakpoints.len() {
+            let Some(entry) = &self.breakpoints[n] else {
+                continue;
+            };
+            let held = self.condition_holds(&entry.breakpoint);
+            let triggered = match entry.breakpoint {
+                Breakpoint::At {
+                    addr: Some(addr), ..
+                } => addr == pc && held,
+                Breakpoint::At { addr: None, .. } => held && !entry.held,
+                Breakpoint::Watch(_) => false,
+            };
+            self.breakpoints[n].as_mut().unwrap().held = held;
+            if triggered && hit.is_none() && self.paused_at != Some(pc) {
+                hit = Some(n);
+            }
+        }
+        hit
+    }
+
+    //the watchpoints on addresses the last step wrote to
+    fn watched(&self) -> Vec<(usize, u16)> {
+        let Some(written) = self.vm.written() else {
+            return Vec::new();
+        };
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(n, entry)| match entry.as_ref()?.breakpoint {
+                Breakpoint::Watch(addr) if written.contains(&(addr as usize)) => Some((n, addr)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn show_current(&self) {
+        let pc = self.vm.pc();
+        match self.vm.read_opcode(pc) {
+            Some(opcode) => match decode(opcode) {
+                Some(instruction) => println!("{pc:#06X}: {opcode:04X}  {instruction}"),
+                None => println!("{pc:#06X}: {opcode:04X}  ???"),
+            },
+            None => println!("{pc:#06X}: out of bounds"),
+        }
+    }
+
+    fn show_registers(&self) {
+        let regs = self.vm.registers();
+        for (r, val) in regs.iter().enumerate() {
+            print!("v{r:X}={val:02X} ");
+            if r % 8 == 7 {
+                println!();
+            }
+        }
+        let (delay, sound) = self.vm.timers();
+        println!(
+            "pc={:04X} i={:04X} delay={delay} sound={sound}",
+            self.vm.pc(),
+            self.vm.index()
+        );
+        let stack: Vec<String> = self.vm.stack().iter().map(|a| format!("{a:04X}")).collect();
+        println!("stack=[{}]", stack.join(", "));
+    }
+
+    fn show_memory(&self, addr: u16, len: u16) {
+        let start = addr as usize;
+        let end = (start + len as usize).min(self.vm.memory().len());
+        for (i, row) in self.vm.memory()[start.min(end)..end].chunks(16).enumerate() {
+            let bytes: Vec<String> = row.iter().map(|b| format!("{b:02X}")).collect();
+            println!("{:04X}: {}", start + i * 16, bytes.join(" "));
+        }
+    }
+}
+
+impl Interpreter for Debugger {
+    fn step(&mut self, keys: &Keys) -> Result<Option<Display>, Error> {
+        let commands: Vec<Command> = self.commands.try_iter().collect();
+        for command in commands {
+            self.handle(command);
+        }
+        if self.mode == Mode::Paused {
+            return Ok(None);
+        }
+        if let Some(e) = self.fault.take() {
+            return Err(e);
+        }
+
+        if let Some(n) = self.check_breakpoints() {
+            println!("Hit breakpoint {n}");
+            self.pause();
+            self.show_current();
+            return Ok(None);
+        }
+        self.paused_at = None;
+
+        let update = match self.vm.step(keys) {
+            Ok(update) => update,
+            Err(e) => {
+                println!("{e}");
+                self.fault = Some(e);
+                self.pause();
+                return Ok(None);
+            }
+        };
+
+        for (n, addr) in self.watched() {
+            let value = self.vm.memory()[addr as usize];
+            println!("Watchpoint {n}: {addr:#06X} written with {value:#04X}");
+            self.pause();
+        }
+
+        let stopped = match self.mode {
+            Mode::Stepping(n) => {
+                self.mode = Mode::Stepping(n.saturating_sub(1));
+                n <= 1
+            }
+            Mode::RunTo { addr, depth } => self.vm.pc() == addr && self.vm.stack().len() == depth,
+            Mode::Finish(depth) => self.vm.stack().len() < depth,
+            Mode::Running | Mode::Paused => false,
+        };
+        if stopped {
+            self.pause();
+        }
+        if self.mode == Mode::Paused {
+            self.show_current();
+        }
+        Ok(update)
+    }
+
+    fn speed(&self) -> Duration {
+        self.vm.speed()
+    }
+
+    //time stands still while paused
+    fn timer_tick(&mut self) {
+        if self.mode != Mode::Paused {
+            self.vm.timer_tick();
+        }
+    }
+
+    //commands are only read in step, so that has to keep being called while paused
+    fn waiting_for_vblank(&self) -> bool {
+        self.mode != Mode::Paused && self.vm.waiting_for_vblank()
+    }
+
+    fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    fn buzzer_active(&self) -> bool {
+        self.mode != Mode::Paused && self.vm.buzzer_active()
+    }
+
+    fn audio_pattern(&self) -> Option<AudioPattern> {
+        self.vm.audio_pattern()
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Interpreter::save_state(&self.vm)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        Interpreter::load_state(&mut self.vm, state)
+    }
+}
@@ -0,0 +1,107 @@
+#![cfg(test)]
+use super::*;
+use crate::interpreter::Quirks;
+use command::{parse, Condition, Op};
+
+fn ibm_logo() -> (Debugger, mpsc::Sender<Command>) {
+    let rom = concat!(env!("CARGO_MANIFEST_DIR"), "/../roms/IBM Logo.ch8");
+    let vm = VM::new(700, Quirks::SCHIP)
+        .load(&std::fs::read(rom).unwrap())
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+    (Debugger::with_commands(vm, rx), tx)
+}
+
+// test that commands are parsed, and bad ones are rejected
+#[test]
+fn test_parse() {
+    assert_eq!(parse("s 10"), Ok(Command::Step(10)));
+    assert_eq!(
+        parse("b 0x2a0"),
+        Ok(Command::Break(Breakpoint::At {
+            addr: Some(0x2A0),
+            condition: None
+        }))
+    );
+    assert_eq!(
+        parse("break 200 if vA >= 0x10"),
+        Ok(Command::Break(Breakpoint::At {
+            addr: Some(0x200),
+            condition: Some(Condition {
+                operand: Operand::Register(0xA),
+                op: Op::Ge,
+                value: 0x10
+            })
+        }))
+    );
+    assert_eq!(
+        parse("watch 300"),
+        Ok(Command::Break(Breakpoint::Watch(0x300)))
+    );
+    assert!(parse("break if vG == 1").is_err());
+    assert!(parse("step over").is_err());
+}
+
+// test that the debugger starts paused, and can step and run to a breakpoint
+#[test]
+fn test_step_and_break() {
+    let (mut debugger, tx) = ibm_logo();
+    debugger.step(&[false; 16]).unwrap();
+    assert_eq!(debugger.vm.pc(), 0x200);
+
+    tx.send(Command::Step(2)).unwrap();
+    for _ in 0..5 {
+        debugger.step(&[false; 16]).unwrap();
+    }
+    assert_eq!(debugger.vm.pc(), 0x204);
+
+    tx.send(parse("b 20e").unwrap()).unwrap();
+    tx.send(Command::Continue).unwrap();
+    for _ in 0..20 {
+        debugger.step(&[false; 16]).unwrap();
+    }
+    assert_eq!(debugger.mode, Mode::Paused);
+    assert_eq!(debugger.vm.pc(), 0x20E);
+}
+
+// test that conditional breakpoints break when their condition becomes true
+#[test]
+fn test_conditional_break() {
+    let (mut debugger, tx) = ibm_logo();
+    tx.send(parse("b if v0 == 0x15").unwrap()).unwrap();
+    tx.send(Command::Continue).unwrap();
+    for _ in 0..20 {
+        debugger.step(&[false; 16]).unwrap();
+    }
+    assert_eq!(debugger.mode, Mode::Paused);
+    assert_eq!(debugger.vm.registers()[0], 0x15);
+    assert_eq!(debugger.vm.pc(), 0x20C);
+}
+
+// test that watchpoints break on writes, even of the value that's already there
+#[test]
+fn test_watch() {
+    let rom = [0x60, 0x00, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06];
+    let vm = VM::new(700, Quirks::default()).load(&rom).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let mut debugger = Debugger::with_commands(vm, rx);
+    tx.send(parse("w 301").unwrap()).unwrap();
+    tx.send(parse("w 300").unwrap()).unwrap();
+    tx.send(Command::Continue).unwrap();
+    for _ in 0..20 {
+        debugger.step(&[false; 16]).unwrap();
+    }
+    assert_eq!(debugger.mode, Mode::Paused);
+    assert_eq!(debugger.vm.pc(), 0x206);
+    assert_eq!(debugger.watched(), [(1, 0x300)]);
+}
+
+// test that quitting asks the frontend to stop, rather than exiting the process
+#[test]
+fn test_quit() {
+    let (mut debugger, tx) = ibm_logo();
+    assert!(!debugger.quit_requested());
+    tx.send(parse("q").unwrap()).unwrap();
+    debugger.step(&[false; 16]).unwrap();
+    assert!(debugger.quit_requested());
+}
@@ -0,0 +1,187 @@
+//recursive descent disassembler, which follows control flow from the entry point to tell code apart from data
+//anything that isn't reached is assumed to be data, so code only reached by computed jumps will show up as bytes
+
+mod test;
+
+use crate::interpreter::instruction::{decode, Instruction, Instruction::*};
+use crate::interpreter::MemoryError;
+use std::collections::BTreeMap;
+
+/// The address ROMs are loaded at, which is where execution starts
+pub const ORIGIN: u16 = 0x200;
+
+//the most data bytes to put on one line
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Data,
+    //the first byte of an instruction
+    Code,
+    //the rest of an instruction
+    Operand,
+}
+
+/// Disassembles a ROM loaded at [`ORIGIN`] into source that the assembler will turn back into the same bytes.
+/// ROMs too big to fit in memory after [`ORIGIN`] are rejected, as they would be when running them
+pub fn disassemble(rom: &[u8]) -> Result<String, MemoryError> {
+    let max = 0x10000 - ORIGIN as usize;
+    if rom.len() > max {
+        return Err(MemoryError::TooLarge {
+            size: rom.len(),
+            max,
+        });
+    }
+    let kinds = trace(rom);
+    let labels = labels(rom, &kinds);
+
+    let mut out = format!("; {} bytes, loaded at {ORIGIN:#05X}\n", rom.len());
+    let mut offset = 0;
+    while offset < rom.len() {
+        let addr = ORIGIN + offset as u16;
+        if let Some(label) = labels.get(&addr) {
+            out.push_str(&format!("{label}:\n"));
+        }
+        let (text, len) = match instruction_at(rom, addr) {
+            Some((instruction, len)) if kinds[offset] == Kind::Code => {
+                (render(rom, addr, instruction, &labels), len as usize)
+            }
+            _ => {
+                //run the data on until the next label or instruction
+                let max = DATA_PER_LINE.min(rom.len() - offset);
+                let len = (1..max)
+                    .find(|&n| {
+                        kinds[offset + n] != Kind::Data || labels.contains_key(&(addr + n as u16))
+                    })
+                    .unwrap_or(max);
+                let bytes: Vec<String> = rom[offset..offset + len]
+                    .iter()
+                    .map(|b| format!("{b:#04X}"))
+                    .collect();
+                (format!("db {}", bytes.join(", ")), len)
+            }
+        };
+        let raw: Vec<String> = rom[offset..offset + len]
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect();
+        out.push_str(&format!("    {text:<40}; {addr:#05X}  {}\n", raw.join(" ")));
+        offset += len;
+    }
+    Ok(out)
+}
+
+//the big-endian word at an address, if it is in the ROM
+fn word(rom: &[u8], addr: u16) -> Option<u16> {
+    let offset = addr.checked_sub(ORIGIN)? as usize;
+    let bytes = rom.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+//the address in the word after a longi, if it's in the ROM
+fn long_operand(rom: &[u8], addr: u16) -> Option<u16> {
+    word(rom, addr.checked_add(2)?)
+}
+
+//the instruction at an address and how many bytes long it is, if there is a valid one there
+//0nnn machine code calls are never used by real programs, so they're treated as data
+fn instruction_at(rom: &[u8], addr: u16) -> Option<(Instruction, u16)> {
+    let opcode = word(rom, addr)?;
+    match decode(opcode)? {
+        Nop if opcode != 0 => None,
+        Longi => long_operand(rom, addr).map(|_| (Longi, 4)),
+        instruction => Some((instruction, 2)),
+    }
+}
+
+//follow every path through the program from the entry point, marking which bytes are code
+fn trace(rom: &[u8]) -> Vec<Kind> {
+    let mut kinds = vec![Kind::Data; rom.len()];
+    let mut pending = vec![ORIGIN];
+    while let Some(addr) = pending.pop() {
+        let Some((instruction, len)) = instruction_at(rom, addr) else {
+            continue;
+        };
+        let offset = (addr - ORIGIN) as usize;
+        let bytes = &mut kinds[offset..offset + len as usize];
+        //already traced, or overlaps another instruction
+        if bytes.iter().any(|k| *k != Kind::Data) {
+            continue;
+        }
+        bytes.fill(Kind::Operand);
+        bytes[0] = Kind::Code;
+
+        //running off the end of memory doesn't lead anywhere
+        let Some(next) = addr.checked_add(len) else {
+            continue;
+        };
+        match instruction {
+            Ret | Exit => (),
+            //a computed jump, so only the first target is known
+            Jmp(target) | Jumpi(target) => pending.push(target),
+            Call(target) => pending.extend([target, next]),
+            Ske(..) | Skne(..) | Skre(..) | Skrne(..) | Skp(_) | Sknp(_) => {
+                let skipped = instruction_at(rom, next).map_or(2, |(_, len)| len);
+                pending.push(next);
+                pending.extend(next.checked_add(skipped));
+            }
+            _ => pending.push(next),
+        }
+    }
+    kinds
+}
+
+//name every address that traced code refers to, as long as it's the start of a line in the output
+//calls are the most useful thing to know about an address, then jumps, so they take priority
+fn labels(rom: &[u8], kinds: &[Kind]) -> BTreeMap<u16, String> {
+    const PREFIXES: [&str; 3] = ["data", "label", "sub"];
+    let mut labels = BTreeMap::new();
+    for (offset, _) in kinds.iter().enumerate().filter(|(_, k)| **k == Kind::Code) {
+        let addr = ORIGIN + offset as u16;
+        let (target, rank) = match instruction_at(rom, addr) {
+            Some((Call(target), _)) => (target, 2),
+            Some((Jmp(target) | Jumpi(target), _)) => (target, 1),
+            Some((Seti(target), _)) => (target, 0),
+            Some((Longi, _)) => match long_operand(rom, addr) {
+                Some(target) => (target, 0),
+                None => continue,
+            },
+            _ => continue,
+        };
+        let in_rom = target
+            .checked_sub(ORIGIN)
+            .and_then(|offset| kinds.get(offset as usize))
+            .is_some_and(|k| *k != Kind::Operand);
+        if in_rom {
+            let best = labels.entry(target).or_insert(rank);
+            *best = rank.max(*best);
+        }
+    }
+    labels
+        .into_iter()
+        .map(|(addr, rank)| (addr, format!("{}_{addr:03X}", PREFIXES[rank])))
+        .collect()
+}
+
+//an instruction as assembly, referring to addresses by their labels where they have one
+fn render(
+    rom: &[u8],
+    addr: u16,
+    instruction: Instruction,
+    labels: &BTreeMap<u16, String>,
+) -> String {
+    let m = instruction.mnemonic();
+    match instruction {
+        Jmp(a) | Call(a) | Seti(a) | Jumpi(a) if labels.contains_key(&a) => {
+            format!("{m} {}", labels[&a])
+        }
+        Longi => match long_operand(rom, addr) {
+            Some(a) => match labels.get(&a) {
+                Some(label) => format!("{m} {label}"),
+                None => format!("{m} {a:#06X}"),
+            },
+            None => instruction.to_string(),
+        },
+        _ => instruction.to_string(),
+    }
+}
@@ -0,0 +1,88 @@
+#![cfg(test)]
+use super::*;
+
+// test that instructions print as assembly
+#[test]
+fn test_display() {
+    assert_eq!(Setr(3, 0x1f).to_string(), "setr v3, 0x1F");
+    assert_eq!(Draw(0, 1, 5).to_string(), "draw v0, v1, 5");
+    assert_eq!(Jmp(0x2a0).to_string(), "jmp 0x2A0");
+    assert_eq!(Shl(0xa, 0xb).to_string(), "shl vA, vB");
+    assert_eq!(Ldfnt(0xf).to_string(), "ldfnt vF");
+    assert_eq!(Scd(4).to_string(), "scd 4");
+    assert_eq!(Cls.to_string(), "cls");
+}
+
+// test that the IBM logo is split into code and sprite data, with labels
+#[test]
+fn test_ibm_logo() {
+    let rom = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../roms/IBM Logo.ch8")).unwrap();
+    let source = disassemble(&rom).unwrap();
+    let lines: Vec<&str> = source
+        .lines()
+        .map(|l| l.split(';').next().unwrap().trim())
+        .collect();
+
+    assert_eq!(lines[1], "cls");
+    assert_eq!(lines[2], "seti data_22A");
+    assert_eq!(lines[5], "draw v0, v1, 15");
+    assert!(lines.contains(&"label_228:"));
+    assert!(lines.contains(&"jmp label_228"));
+    let data = lines.iter().position(|l| *l == "data_22A:").unwrap();
+    assert_eq!(
+        lines[data + 1],
+        "db 0xFF, 0x00, 0xFF, 0x00, 0x3C, 0x00, 0x3C, 0x00"
+    );
+    assert!(lines[data + 1..]
+        .iter()
+        .all(|l| l.starts_with("db") || l.ends_with(':')));
+    assert!(source.contains("; 0x200  00 E0\n"));
+}
+
+// test that both sides of skips and calls are traced, and longi skips its operand
+#[test]
+fn test_trace() {
+    let rom = [
+        0x22, 0x08, //0x200 call sub_208
+        0x30, 0x00, //0x202 ske v0, 0x00
+        0xF0, 0x00, 0x02, 0x0E, //0x204 longi data_20E
+        0x12, 0x08, //0x208 jmp sub_208
+        0x00, 0xEE, //0x20A ret, unreachable
+        0x00, 0xEE, //0x20C
+        0xAB, 0xCD, //0x20E
+    ];
+    let kinds = trace(&rom);
+    assert_eq!(kinds[4], Kind::Code);
+    assert_eq!(kinds[6], Kind::Operand);
+    assert_eq!(kinds[8], Kind::Code);
+    assert_eq!(kinds[10], Kind::Data);
+    assert_eq!(kinds[14], Kind::Data);
+
+    let source = disassemble(&rom).unwrap();
+    assert!(source.contains("sub_208:\n    jmp sub_208"));
+    assert!(source.contains("longi data_20E"));
+    assert!(source.contains("data_20E:\n    db 0xAB, 0xCD"));
+}
+
+// test that ROMs too big for memory are rejected, and code running into the end of memory doesn't overflow
+#[test]
+fn test_end_of_memory() {
+    let max = 0x10000 - ORIGIN as usize;
+    assert_eq!(
+        disassemble(&vec![0; max + 1]),
+        Err(MemoryError::TooLarge { size: max + 1, max })
+    );
+
+    //cls all the way to the end, apart from a longi in the last word with nowhere for its operand
+    let mut rom = [0x00, 0xE0].repeat(max / 2);
+    rom[max - 2..].copy_from_slice(&[0xF0, 0x00]);
+    let kinds = trace(&rom);
+    assert_eq!(kinds[max - 4], Kind::Code);
+    assert_eq!(kinds[max - 2..], [Kind::Data, Kind::Data]);
+    let source = disassemble(&rom).unwrap();
+    assert!(source.ends_with("    db 0xF0, 0x00                           ; 0xFFFE  F0 00\n"));
+
+    //a longi at the end of a ROM, with its operand cut off
+    let source = disassemble(&[0x00, 0xE0, 0xF0, 0x00]).unwrap();
+    assert!(source.contains("    db 0xF0, 0x00"));
+}
@@ -0,0 +1,56 @@
+//instructions are decoded the first time they run and kept by address, so loops don't decode the same opcodes every time
+//anything that writes to memory has to throw away what it wrote over, or self-modifying code would run stale instructions
+
+use super::instruction::Instruction;
+use super::MEMORY_SIZE;
+use std::fmt;
+
+//the opcode at an address, and what it decodes to, which is None if it isn't a valid instruction
+pub type Decoded = (u16, Option<Instruction>);
+
+#[derive(Clone, Default)]
+pub struct DecodeCache {
+    //indexed by address, and left empty until something is decoded so VMs that never run don't pay for it
+    entries: Vec<Option<Decoded>>,
+}
+
+impl DecodeCache {
+    pub fn get(&self, addr: u16) -> Option<Decoded> {
+        self.entries.get(addr as usize).copied().flatten()
+    }
+
+    pub fn insert(&mut self, addr: u16, decoded: Decoded) -> Decoded {
+        if self.entries.is_empty() {
+            self.entries.resize(MEMORY_SIZE, None);
+        }
+        self.entries[addr as usize] = Some(decoded);
+        decoded
+    }
+
+    //forget the instructions decoded from the bytes start..start + len, which includes the one starting the byte before
+    pub fn invalidate(&mut self, start: usize, len: usize) {
+        let end = (start + len).min(self.entries.len());
+        let start = start.saturating_sub(1).min(end);
+        self.entries[start..end].fill(None);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+//the cache only ever holds what's already in memory, so it never makes two VMs different
+impl PartialEq for DecodeCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for DecodeCache {}
+
+impl fmt::Debug for DecodeCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cached = self.entries.iter().filter(|e| e.is_some()).count();
+        write!(f, "DecodeCache({cached} instructions)")
+    }
+}
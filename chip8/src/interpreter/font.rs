@@ -1,3 +1,164 @@
+//the glyphs Fx29 and Fx30 point into, which each interpreter brought its own design of
+//most programs only draw scores with them, but some were written for the look of a particular machine's font
+//glyphs are a byte per row, with the leftmost pixel in the most significant bit
+
+use std::path::Path;
+
+//bytes per glyph in the 4x5 and 8x10 fonts
+pub const SMALL_GLYPH: usize = 5;
+pub const BIG_GLYPH: usize = 10;
+
+/// The glyphs for the 16 hex digits, in both sizes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    pub small: [u8; 16 * SMALL_GLYPH],
+    pub big: [u8; 16 * BIG_GLYPH],
+}
+
+impl Font {
+    /// The font most modern interpreters use, from CHIP-48, with Octo's big font
+    pub const OCTO: Font = Font {
+        small: FONT,
+        big: BIG_FONT,
+    };
+
+    /// The built in fonts by name. Machines from before SUPER-CHIP had no big font, so they get Octo's
+    pub const BUILT_IN: [(&'static str, Font); 6] = [
+        ("octo", Font::OCTO),
+        (
+            "vip",
+            Font {
+                small: VIP_FONT,
+                big: BIG_FONT,
+            },
+        ),
+        (
+            "dream6800",
+            Font {
+                small: DREAM_6800_FONT,
+                big: BIG_FONT,
+            },
+        ),
+        (
+            "eti660",
+            Font {
+                small: ETI_660_FONT,
+                big: BIG_FONT,
+            },
+        ),
+        (
+            "fish",
+            Font {
+                small: FISH_N_CHIPS_FONT,
+                big: BIG_FONT,
+            },
+        ),
+        (
+            "schip",
+            Font {
+                small: FONT,
+                big: SCHIP_BIG_FONT,
+            },
+        ),
+    ];
+
+    /// Looks up a built in font by name, ignoring case and any `-` or `_`
+    pub fn named(name: &str) -> Option<Font> {
+        let name = name.to_lowercase().replace(['-', '_'], "");
+        Self::BUILT_IN
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, font)| font.clone())
+    }
+
+    /// Reads a font from a file of raw glyphs, or a PNG if it has a `.png` extension.
+    ///
+    /// Raw fonts are the 80 bytes of the small font, the 160 bytes of the big one, or both in that order.
+    /// PNGs are the 16 glyphs side by side, 8 pixels each, and 5 pixels tall for the small font, 10 for the big one, or 15 for both.
+    /// Light pixels are set and dark or transparent ones aren't. Whichever size isn't in the file is Octo's.
+    pub fn load(path: &Path) -> Result<Font, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+        let glyphs = match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("png") => {
+                png_glyphs(&bytes).map_err(|e| format!("Could not read {}: {e}", path.display()))?
+            }
+            _ => bytes,
+        };
+        Font::from_glyphs(&glyphs).ok_or_else(|| {
+            format!(
+                "{} has {} bytes of glyphs, but fonts have 80, 160 or 240",
+                path.display(),
+                glyphs.len()
+            )
+        })
+    }
+
+    fn from_glyphs(glyphs: &[u8]) -> Option<Font> {
+        let mut font = Font::OCTO;
+        match glyphs.len() {
+            80 => font.small.copy_from_slice(glyphs),
+            160 => font.big.copy_from_slice(glyphs),
+            240 => {
+                font.small.copy_from_slice(&glyphs[..80]);
+                font.big.copy_from_slice(&glyphs[80..]);
+            }
+            _ => return None,
+        }
+        Some(font)
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::OCTO
+    }
+}
+
+//read the glyphs out of a strip of them in a PNG, small glyphs then big ones
+fn png_glyphs(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    if width != 16 * 8 || ![5, 10, 15].contains(&height) {
+        return Err(format!(
+            "font images are 128 pixels wide and 5, 10 or 15 tall, not {width}x{height}"
+        ));
+    }
+    //colours are averaged, and alpha is the last channel if there is one
+    let channels = info.color_type.samples();
+    let colours = if channels % 2 == 0 {
+        channels - 1
+    } else {
+        channels
+    };
+    let lit = |x: usize, y: usize| {
+        let px = &buf[y * info.line_size + x * channels..][..channels];
+        let brightness = px[..colours].iter().map(|c| *c as usize).sum::<usize>() / colours;
+        let opaque = colours == channels || px[channels - 1] >= 128;
+        brightness >= 128 && opaque
+    };
+    let sizes: &[usize] = match height {
+        5 => &[SMALL_GLYPH],
+        10 => &[BIG_GLYPH],
+        _ => &[SMALL_GLYPH, BIG_GLYPH],
+    };
+    let (mut glyphs, mut top) = (Vec::new(), 0);
+    for size in sizes {
+        for glyph in 0..16 {
+            for y in top..top + size {
+                glyphs.push((0..8).fold(0, |byte, x| byte << 1 | lit(glyph * 8 + x, y) as u8));
+            }
+        }
+        top += size;
+    }
+    Ok(glyphs)
+}
+
+//the common 4x5 font, from CHIP-48
 pub const FONT: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
@@ -16,3 +177,123 @@ pub const FONT: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
+
+//Octo's 8x10 font, which has all of the hex digits where SUPER-CHIP's only had 0-9
+pub const BIG_FONT: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+//the COSMAC VIP's font, which shared rows between glyphs to fit in its interpreter's ROM
+const VIP_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+//the DREAM 6800's font, 3 pixels wide
+const DREAM_6800_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+//the ETI-660's font, with lowercase b and d
+const ETI_660_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x20, 0x20, 0x20, 0x20, 0x20, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0x80, 0x80, 0xE0, 0xA0, 0xE0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0x20, 0x20, 0xE0, 0xA0, 0xE0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xE0, 0x80, 0x80, // F
+];
+
+//FISH 'N' CHIPS's font, with rounded glyphs
+const FISH_N_CHIPS_FONT: [u8; 80] = [
+    0x60, 0xA0, 0xA0, 0xA0, 0xC0, // 0
+    0x40, 0xC0, 0x40, 0x40, 0xE0, // 1
+    0xC0, 0x20, 0x40, 0x80, 0xE0, // 2
+    0xC0, 0x20, 0x40, 0x20, 0xC0, // 3
+    0x20, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xC0, 0x20, 0xC0, // 5
+    0x40, 0x80, 0xC0, 0xA0, 0x40, // 6
+    0xE0, 0x20, 0x60, 0x40, 0x40, // 7
+    0x40, 0xA0, 0x40, 0xA0, 0x40, // 8
+    0x40, 0xA0, 0x60, 0x20, 0x40, // 9
+    0x40, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xC0, 0xA0, 0xC0, // B
+    0x60, 0x80, 0x80, 0x80, 0x60, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xC0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+//SUPER-CHIP 1.1's 8x10 font, which only had digits, so A-F are Octo's
+const SCHIP_BIG_FONT: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
@@ -1,48 +1,65 @@
-use super::{eightbit, nibbles, twelvebit};
+use super::{eightbit, nibbles, twelvebit, Platform};
+use std::fmt;
 
 type Reg = u8;
 type Addr = u16;
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     Nop,                //0nnn, sys instruction on original machines but not used anymore
     Cls,                //00E0, clear display
     Ret,                //00EE, return from a subroutine
+    Scd(u8),            //00Cn, scroll the display down n pixels (SCHIP)
+    Scu(u8),            //00Dn, scroll the display up n pixels (XO-CHIP)
+    Scr,                //00FB, scroll the display right 4 pixels (SCHIP)
+    Scl,                //00FC, scroll the display left 4 pixels (SCHIP)
+    Exit,               //00FD, exit the interpreter (SCHIP)
+    Lores,              //00FE, switch to 64x32 low resolution mode (SCHIP)
+    Hires,              //00FF, switch to 128x64 high resolution mode (SCHIP)
     Jmp(Addr),          //1nnn, jump to address nnn
     Call(Addr),         //2nnn, call subroutine at nnn
     Ske(Reg, u8),       //3xkk, skip the next instruction if Vx == kk
     Skne(Reg, u8),      //4xkk, skip the next instruction if Vx != kk
     Skre(Reg, Reg),     //5xy0, skip the next instruction if Vx == Vy
-    Setr(Reg, u8),      //6xkk, put value kk in register x
-    Addr(Reg, u8),      //7xkk, Adds the value kk to the value of register Vx
-    Move(Reg, Reg),     //8xy0, Vx = Vy,
-    Or(Reg, Reg),       //8xy1, Vx = Vx OR Vy
-    And(Reg, Reg),      //8xy2, Vx = Vx AND Vy
-    Xor(Reg, Reg),      //8xy3, Vx = Vx XOR Vy
-    Add(Reg, Reg),      //8xy4, Vx = Vx + Vy
-    Sub(Reg, Reg),      //8xy5, Vx = Vx - Vy
-    Shr(Reg, Reg),      //8xy6, Vx = Vy >> 1
-    Ssub(Reg, Reg),     //8xy7 Vx = Vy - Vx
-    Shl(Reg, Reg),      //8xyE, Vx = Vy << 1
-    Skrne(Reg, Reg),    //9xy0, skip the next instruction if Vx != Vy
-    Seti(u16),          //Annn, set index register to value nnn
-    Jumpi(u16),         //Bnnn, jump to the instruction in index register, + offset nnn
-    Rand(Reg, u8),      //Cxkk, Vx = rand() & kk
-    Draw(Reg, Reg, u8), //Dxyn, display n byte-sprite starting at memory location I at coordinate (vx,vy), set VF = collision?
+    Savr(Reg, Reg), //5xy2, store registers Vx to Vy at the address in the index register (XO-CHIP)
+    Loadr(Reg, Reg), //5xy3, load registers Vx to Vy from the address in the index register (XO-CHIP)
+    Setr(Reg, u8),   //6xkk, put value kk in register x
+    Addr(Reg, u8),   //7xkk, Adds the value kk to the value of register Vx
+    Move(Reg, Reg),  //8xy0, Vx = Vy,
+    Or(Reg, Reg),    //8xy1, Vx = Vx OR Vy
+    And(Reg, Reg),   //8xy2, Vx = Vx AND Vy
+    Xor(Reg, Reg),   //8xy3, Vx = Vx XOR Vy
+    Add(Reg, Reg),   //8xy4, Vx = Vx + Vy
+    Sub(Reg, Reg),   //8xy5, Vx = Vx - Vy
+    Shr(Reg, Reg),   //8xy6, Vx = Vy >> 1
+    Ssub(Reg, Reg),  //8xy7 Vx = Vy - Vx
+    Shl(Reg, Reg),   //8xyE, Vx = Vy << 1
+    Skrne(Reg, Reg), //9xy0, skip the next instruction if Vx != Vy
+    Seti(u16),       //Annn, set index register to value nnn
+    Jumpi(u16),      //Bnnn, jump to the instruction in index register, + offset nnn
+    Rand(Reg, u8),   //Cxkk, Vx = rand() & kk
+    Draw(Reg, Reg, u8), //Dxyn, display n byte-sprite starting at memory location I at coordinate (vx,vy), set VF = collision? Dxy0 draws a 16x16 sprite (SCHIP)
     Skp(Reg), //Ex9E, skip the next instruction if the key with the value in Vx is currently pressed down
     Sknp(Reg), //ExA1, skip the next instruction if the key with the value in Vx is NOT currently pressed down
+    Longi, //F000 nnnn, load index register with the 16-bit address nnnn in the following word (XO-CHIP)
+    Plane(u8), //Fn01, select the bitplanes n to draw to (XO-CHIP)
+    Audio, //F002, load the 16 byte audio pattern buffer from the address in the index register (XO-CHIP)
     Moved(Reg), //Fx07, Vx = display timer
-    Key(Reg),  //Fx0A, block while waiting for keypress, then store the key pressed in Vx
+    Key(Reg), //Fx0A, block while waiting for keypress, then store the key pressed in Vx
     Setrd(Reg), //Fx15, display timer = Vx
     Setrs(Reg), //Fx18, sound timer = Vy
     Addi(Reg), //Fx1E, index = Vx + index
     Ldfnt(Reg), //Fx29, load index register with address of the font charachter in Vx
+    Ldbfnt(Reg), //Fx30, load index register with address of the big font charachter in Vx (SCHIP)
+    Pitch(Reg), //Fx3A, set the audio pattern playback pitch to Vx (XO-CHIP)
     Bcd(Reg), //Fx33 Store three bytes represnting the binary-coded decimal value of Vx to the address at index
     Store(Reg), //Fx55 Store all the registers at the address in the index register
     Load(Reg), //Fx65 Load all the registers with values from the address at the index register
+    Strpl(Reg), //Fx75 Store registers V0 to Vx in the RPL user flags (SCHIP)
+    Ldrpl(Reg), //Fx85 Load registers V0 to Vx from the RPL user flags (SCHIP)
 }
 
 use Instruction::*;
-pub fn decode(opcode: u16) -> Instruction {
+pub fn decode(opcode: u16) -> Option<Instruction> {
     //the variable length operands
     //see http://devernay.free.fr/hacks/chip8/C8TECH10.HTM
     let nnn = twelvebit(opcode);
@@ -50,15 +67,24 @@ pub fn decode(opcode: u16) -> Instruction {
 
     //parse the instruction into a structured representation
     //big match
-    match nibbles(opcode) {
+    let instruction = match nibbles(opcode) {
         (0, 0, 0xE, 0xE) => Ret,
         (0, 0, 0xE, 0) => Cls,
+        (0, 0, 0xC, n) => Scd(n),
+        (0, 0, 0xD, n) => Scu(n),
+        (0, 0, 0xF, 0xB) => Scr,
+        (0, 0, 0xF, 0xC) => Scl,
+        (0, 0, 0xF, 0xD) => Exit,
+        (0, 0, 0xF, 0xE) => Lores,
+        (0, 0, 0xF, 0xF) => Hires,
         (0, _, _, _) => Nop,
         (1, _, _, _) => Jmp(nnn),
         (2, _, _, _) => Call(nnn),
         (3, x, _, _) => Ske(x, kk),
         (4, x, _, _) => Skne(x, kk),
         (5, x, y, 0) => Skre(x, y),
+        (5, x, y, 2) => Savr(x, y),
+        (5, x, y, 3) => Loadr(x, y),
         (6, x, _, _) => Setr(x, kk),
         (7, x, _, _) => Addr(x, kk),
         (8, x, y, 0) => Move(x, y),
@@ -77,15 +103,189 @@ pub fn decode(opcode: u16) -> Instruction {
         (0xD, x, y, n) => Draw(x, y, n),
         (0xE, x, 9, 0xE) => Skp(x),
         (0xE, x, 0xA, 1) => Sknp(x),
+        (0xF, 0, 0, 0) => Longi,
+        (0xF, n, 0, 1) => Plane(n),
+        (0xF, 0, 0, 2) => Audio,
         (0xF, x, 0, 7) => Moved(x),
         (0xF, x, 0, 0xA) => Key(x),
         (0xF, x, 1, 5) => Setrd(x),
         (0xF, x, 1, 8) => Setrs(x),
         (0xF, x, 1, 0xE) => Addi(x),
         (0xF, x, 2, 9) => Ldfnt(x),
+        (0xF, x, 3, 0) => Ldbfnt(x),
+        (0xF, x, 3, 0xA) => Pitch(x),
         (0xF, x, 3, 3) => Bcd(x),
         (0xF, x, 5, 5) => Store(x),
         (0xF, x, 6, 5) => Load(x),
-        _ => panic!("Invalid instruction encountered!: {:#06X}", opcode),
+        (0xF, x, 7, 5) => Strpl(x),
+        (0xF, x, 8, 5) => Ldrpl(x),
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+impl Instruction {
+    /// The assembly mnemonic for the instruction, which is its name in lowercase
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Nop => "nop",
+            Cls => "cls",
+            Ret => "ret",
+            Scd(_) => "scd",
+            Scu(_) => "scu",
+            Scr => "scr",
+            Scl => "scl",
+            Exit => "exit",
+            Lores => "lores",
+            Hires => "hires",
+            Jmp(_) => "jmp",
+            Call(_) => "call",
+            Ske(..) => "ske",
+            Skne(..) => "skne",
+            Skre(..) => "skre",
+            Savr(..) => "savr",
+            Loadr(..) => "loadr",
+            Setr(..) => "setr",
+            Addr(..) => "addr",
+            Move(..) => "move",
+            Or(..) => "or",
+            And(..) => "and",
+            Xor(..) => "xor",
+            Add(..) => "add",
+            Sub(..) => "sub",
+            Shr(..) => "shr",
+            Ssub(..) => "ssub",
+            Shl(..) => "shl",
+            Skrne(..) => "skrne",
+            Seti(_) => "seti",
+            Jumpi(_) => "jumpi",
+            Rand(..) => "rand",
+            Draw(..) => "draw",
+            Skp(_) => "skp",
+            Sknp(_) => "sknp",
+            Longi => "longi",
+            Plane(_) => "plane",
+            Audio => "audio",
+            Moved(_) => "moved",
+            Key(_) => "key",
+            Setrd(_) => "setrd",
+            Setrs(_) => "setrs",
+            Addi(_) => "addi",
+            Ldfnt(_) => "ldfnt",
+            Ldbfnt(_) => "ldbfnt",
+            Pitch(_) => "pitch",
+            Bcd(_) => "bcd",
+            Store(_) => "store",
+            Load(_) => "load",
+            Strpl(_) => "strpl",
+            Ldrpl(_) => "ldrpl",
+        }
+    }
+
+    /// The first platform to have the instruction, which every platform after it has too
+    pub fn platform(&self) -> Platform {
+        match self {
+            Scd(_) | Scr | Scl | Exit | Lores | Hires | Ldbfnt(_) | Strpl(_) | Ldrpl(_) => {
+                Platform::Schip
+            }
+            Scu(_) | Savr(..) | Loadr(..) | Longi | Plane(_) | Audio | Pitch(_) => Platform::Xochip,
+            _ => Platform::Vip,
+        }
+    }
+}
+
+//prints the instruction as assembly, eg `setr v3, 0x1F` or `draw v0, v1, 5`
+//the operand of longi is in the following word, so it isn't printed here
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.mnemonic();
+        match *self {
+            Nop | Cls | Ret | Scr | Scl | Exit | Lores | Hires | Longi | Audio => write!(f, "{m}"),
+            Scd(n) | Scu(n) | Plane(n) => write!(f, "{m} {n}"),
+            Jmp(a) | Call(a) | Seti(a) | Jumpi(a) => write!(f, "{m} {a:#05X}"),
+            Ske(x, kk) | Skne(x, kk) | Setr(x, kk) | Addr(x, kk) | Rand(x, kk) => {
+                write!(f, "{m} v{x:X}, {kk:#04X}")
+            }
+            Skre(x, y)
+            | Savr(x, y)
+            | Loadr(x, y)
+            | Move(x, y)
+            | Or(x, y)
+            | And(x, y)
+            | Xor(x, y)
+            | Add(x, y)
+            | Sub(x, y)
+            | Shr(x, y)
+            | Ssub(x, y)
+            | Shl(x, y)
+            | Skrne(x, y) => write!(f, "{m} v{x:X}, v{y:X}"),
+            Draw(x, y, n) => write!(f, "{m} v{x:X}, v{y:X}, {n}"),
+            Skp(x) | Sknp(x) | Moved(x) | Key(x) | Setrd(x) | Setrs(x) | Addi(x) | Ldfnt(x)
+            | Ldbfnt(x) | Pitch(x) | Bcd(x) | Store(x) | Load(x) | Strpl(x) | Ldrpl(x) => {
+                write!(f, "{m} v{x:X}")
+            }
+        }
+    }
+}
+
+impl Instruction {
+    /// The opcode for the instruction, the inverse of [`decode`].
+    /// Operands are masked to the size of their field, and the operand of longi goes in the word after
+    pub fn encode(&self) -> u16 {
+        let x = |x: Reg| (x as u16 & 0xF) << 8;
+        let xy = |x: Reg, y: Reg| (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4;
+        match *self {
+            Nop => 0x0000,
+            Cls => 0x00E0,
+            Ret => 0x00EE,
+            Scd(n) => 0x00C0 | (n as u16 & 0xF),
+            Scu(n) => 0x00D0 | (n as u16 & 0xF),
+            Scr => 0x00FB,
+            Scl => 0x00FC,
+            Exit => 0x00FD,
+            Lores => 0x00FE,
+            Hires => 0x00FF,
+            Jmp(nnn) => 0x1000 | (nnn & 0xFFF),
+            Call(nnn) => 0x2000 | (nnn & 0xFFF),
+            Ske(r, kk) => 0x3000 | x(r) | kk as u16,
+            Skne(r, kk) => 0x4000 | x(r) | kk as u16,
+            Skre(r, s) => 0x5000 | xy(r, s),
+            Savr(r, s) => 0x5002 | xy(r, s),
+            Loadr(r, s) => 0x5003 | xy(r, s),
+            Setr(r, kk) => 0x6000 | x(r) | kk as u16,
+            Addr(r, kk) => 0x7000 | x(r) | kk as u16,
+            Move(r, s) => 0x8000 | xy(r, s),
+            Or(r, s) => 0x8001 | xy(r, s),
+            And(r, s) => 0x8002 | xy(r, s),
+            Xor(r, s) => 0x8003 | xy(r, s),
+            Add(r, s) => 0x8004 | xy(r, s),
+            Sub(r, s) => 0x8005 | xy(r, s),
+            Shr(r, s) => 0x8006 | xy(r, s),
+            Ssub(r, s) => 0x8007 | xy(r, s),
+            Shl(r, s) => 0x800E | xy(r, s),
+            Skrne(r, s) => 0x9000 | xy(r, s),
+            Seti(nnn) => 0xA000 | (nnn & 0xFFF),
+            Jumpi(nnn) => 0xB000 | (nnn & 0xFFF),
+            Rand(r, kk) => 0xC000 | x(r) | kk as u16,
+            Draw(r, s, n) => 0xD000 | xy(r, s) | (n as u16 & 0xF),
+            Skp(r) => 0xE09E | x(r),
+            Sknp(r) => 0xE0A1 | x(r),
+            Longi => 0xF000,
+            Plane(n) => 0xF001 | x(n),
+            Audio => 0xF002,
+            Moved(r) => 0xF007 | x(r),
+            Key(r) => 0xF00A | x(r),
+            Setrd(r) => 0xF015 | x(r),
+            Setrs(r) => 0xF018 | x(r),
+            Addi(r) => 0xF01E | x(r),
+            Ldfnt(r) => 0xF029 | x(r),
+            Ldbfnt(r) => 0xF030 | x(r),
+            Pitch(r) => 0xF03A | x(r),
+            Bcd(r) => 0xF033 | x(r),
+            Store(r) => 0xF055 | x(r),
+            Load(r) => 0xF065 | x(r),
+            Strpl(r) => 0xF075 | x(r),
+            Ldrpl(r) => 0xF085 | x(r),
+        }
     }
 }
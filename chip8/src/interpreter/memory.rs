@@ -0,0 +1,123 @@
+//where the fonts and the program go in memory when the VM starts
+//the COSMAC VIP kept its interpreter in the first 512 bytes and loaded programs straight after it, which most ROMs expect,
+//but the ETI-660 loaded them at 0x600, and some ROMs read or even call into the interpreter that was there
+
+use super::font::{Font, BIG_GLYPH, SMALL_GLYPH};
+use super::MEMORY_SIZE;
+use std::fmt;
+
+//the 4x5 font, with the 8x10 font straight after it
+const FONTS_LEN: usize = 16 * (SMALL_GLYPH + BIG_GLYPH);
+
+/// Where things are placed in the VM's memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    /// The address of the 4x5 hex font that `Fx29` points into, which the 8x10 font used by `Fx30` follows
+    pub font: u16,
+    /// The address the ROM is loaded at, where execution starts
+    pub load: u16,
+    /// An image of the interpreter area, copied to the start of memory before the fonts are
+    pub interpreter: Option<Vec<u8>>,
+    /// The glyphs of the fonts
+    pub glyphs: Font,
+}
+
+impl MemoryMap {
+    /// The usual layout, with the font in the interpreter area at 0x50 and programs loaded at 0x200
+    pub const STANDARD: MemoryMap = MemoryMap {
+        font: 0x50,
+        load: 0x200,
+        interpreter: None,
+        glyphs: Font::OCTO,
+    };
+
+    //where the glyphs for a hex digit are in each font
+    pub fn small_glyph(&self, digit: u8) -> u16 {
+        self.font + (digit as usize * SMALL_GLYPH) as u16
+    }
+
+    pub fn big_glyph(&self, digit: u8) -> u16 {
+        self.font + (16 * SMALL_GLYPH + digit as usize * BIG_GLYPH) as u16
+    }
+
+    //memory as it is before a ROM is loaded, with the interpreter image and fonts in place
+    pub fn memory(&self) -> Result<Vec<u8>, MemoryError> {
+        let mut memory = vec![0; MEMORY_SIZE];
+        if let Some(image) = &self.interpreter {
+            let max = self.load as usize;
+            if image.len() > max {
+                return Err(MemoryError::ImageTooLarge {
+                    size: image.len(),
+                    max,
+                });
+            }
+            memory[..image.len()].copy_from_slice(image);
+        }
+        let font = self.font as usize;
+        if font + FONTS_LEN > MEMORY_SIZE {
+            return Err(MemoryError::FontOutOfBounds(self.font));
+        }
+        let big = font + self.glyphs.small.len();
+        memory[font..big].copy_from_slice(&self.glyphs.small);
+        memory[big..font + FONTS_LEN].copy_from_slice(&self.glyphs.big);
+        Ok(memory)
+    }
+
+    //copy a ROM into memory at the load address, as long as it fits and doesn't run over the fonts
+    pub fn load(&self, memory: &mut [u8], rom: &[u8]) -> Result<(), MemoryError> {
+        let (load, font) = (self.load as usize, self.font as usize);
+        let max = memory.len() - load;
+        if rom.len() > max {
+            return Err(MemoryError::TooLarge {
+                size: rom.len(),
+                max,
+            });
+        }
+        if font < load + rom.len() && load < font + FONTS_LEN {
+            return Err(MemoryError::FontOverlap(self.font));
+        }
+        memory[load..load + rom.len()].copy_from_slice(rom);
+        Ok(())
+    }
+}
+
+impl Default for MemoryMap {
+    fn default() -> Self {
+        MemoryMap::STANDARD
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryError {
+    /// The ROM doesn't fit in memory after the load address
+    TooLarge { size: usize, max: usize },
+    /// The interpreter image runs past the load address
+    ImageTooLarge { size: usize, max: usize },
+    /// The ROM would be loaded over the fonts at this address
+    FontOverlap(u16),
+    /// The fonts would run off the end of memory from this address
+    FontOutOfBounds(u16),
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::TooLarge { size, max } => write!(
+                f,
+                "ROM is {size} bytes, but only {max} fit in memory after the load address"
+            ),
+            MemoryError::ImageTooLarge { size, max } => write!(
+                f,
+                "Interpreter image is {size} bytes, but the ROM is loaded after only {max}"
+            ),
+            MemoryError::FontOverlap(font) => {
+                write!(f, "ROM would be loaded over the fonts at {font:#05X}")
+            }
+            MemoryError::FontOutOfBounds(font) => {
+                write!(f, "Fonts at {font:#05X} don't fit in memory")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
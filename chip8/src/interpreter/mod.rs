@@ -1,18 +1,33 @@
+mod cache;
 mod font;
-mod instruction;
+pub mod instruction;
+mod memory;
+mod quirks;
+mod snapshot;
 mod test;
 
-use chip8_base::{
-    Display, Keys,
-    Pixel::{self, *},
-};
+use cache::{DecodeCache, Decoded};
+use chip8_base::{AudioPattern, Display, Error, ErrorKind, Keys, Pixel, Resolution, PLANES};
+pub use font::Font;
 use instruction::{decode, Instruction};
-use rand::random;
+pub use memory::{MemoryError, MemoryMap};
+pub use quirks::{Platform, Quirks};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::ops::Range;
 use std::time::Duration;
 
+//XO-CHIP extends the address space to 16 bits
+const MEMORY_SIZE: usize = 0x10000;
+//SUPER-CHIP allowed subroutines to nest 16 deep
+const STACK_SIZE: usize = 16;
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct VM {
-    memory: [u8; 4096],
+    memory: Vec<u8>,
+    decoded: DecodeCache,
+    //the memory the last instruction wrote to, if it wrote to any
+    written: Option<Range<usize>>,
     pc: u16,
     index: u16,
     stack: Vec<u16>,
@@ -20,47 +35,96 @@ pub struct VM {
     delay_timer: u8,
     sound_timer: u8,
     speed: Duration,
-    ticker: u32,
-    max_ticks: u32,
-    display: [[Pixel; 64]; 32],
+    //set at the start of each frame, and cleared by drawing when the VM waits for it
+    vblank: bool,
+    display: Display,
+    quirks: Quirks,
+    platform: Platform,
+    memory_map: MemoryMap,
+    rpl: [u8; 16],
+    halted: bool,
+    planes: u8,
+    pattern: Option<[u8; 16]>,
+    pitch: u8,
+    redraw: bool,
+    rng: ChaCha8Rng,
 }
 
 impl chip8_base::Interpreter for VM {
     //this should execute in the time 1/speed
-    fn step(&mut self, keys: &Keys) -> Option<Display> {
-        let opcode = self.fetch();
-        let instruction = decode(opcode);
-        let update = self.execute(instruction, keys);
-
-        //ticker counts up to max_ticks, and at max_ticks the timers are decremented
-        self.ticker += 1;
-        if self.ticker == self.max_ticks {
-            self.ticker = 0;
-            self.delay_timer = self.delay_timer.saturating_sub(1);
-            self.sound_timer = self.sound_timer.saturating_sub(1);
+    fn step(&mut self, keys: &Keys) -> Result<Option<Display>, Error> {
+        //once exited, the VM does nothing until it's restarted
+        if self.halted {
+            return Ok(None);
+        }
+        let pc = self.pc;
+        let error = |kind, opcode| Error { kind, pc, opcode };
+        let (opcode, instruction) = self.fetch_decoded().map_err(|kind| error(kind, None))?;
+        let mut update = instruction
+            .ok_or(ErrorKind::InvalidOpcode)
+            .and_then(|instruction| self.execute(instruction, keys))
+            .map_err(|kind| error(kind, Some(opcode)))?;
+
+        //the display has to be sent out after a save state is loaded, even if nothing was drawn
+        if std::mem::take(&mut self.redraw) {
+            update = Some(self.display);
         }
 
-        update
+        Ok(update)
     }
 
     fn speed(&self) -> Duration {
         self.speed
     }
 
+    //called at 60Hz, which is also when the VIP's vertical blank interrupt happened
+    fn timer_tick(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+        self.vblank = true;
+    }
+
+    fn waiting_for_vblank(&self) -> bool {
+        self.quirks.display_wait
+            && !self.vblank
+            && matches!(
+                match self.decoded.get(self.pc) {
+                    Some((_, instruction)) => instruction,
+                    None => self.read_opcode(self.pc).and_then(decode),
+                },
+                Some(Instruction::Draw(..))
+            )
+    }
+
     fn buzzer_active(&self) -> bool {
         self.sound_timer != 0
     }
-}
 
-impl VM {
-    pub fn new(speed: u32) -> Self {
-        let mut memory = [0_u8; 4096];
+    fn audio_pattern(&self) -> Option<AudioPattern> {
+        self.pattern.map(|buffer| AudioPattern {
+            buffer,
+            pitch: self.pitch,
+        })
+    }
 
-        //font is 80 bytes, should lie at 0x50
-        memory[0x50..(0x50 + 80)].copy_from_slice(&font::FONT);
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(VM::save_state(self))
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(VM::load_state(self, state)?)
+    }
+}
 
+impl VM {
+    pub fn new(speed: u32, quirks: Quirks) -> Self {
+        let memory_map = MemoryMap::default();
         VM {
-            memory: [0; 4096],
+            memory: memory_map
+                .memory()
+                .expect("the standard memory map is valid"),
+            decoded: DecodeCache::default(),
+            written: None,
             pc: 0,
             index: 0,
             delay_timer: 0,
@@ -68,43 +132,162 @@ impl VM {
             stack: Vec::new(),
             registers: [0; 16],
             speed: Duration::from_secs_f64(1_f64 / speed as f64),
-            ticker: 0,
-            max_ticks: (speed as f64 / 60_f64).round() as u32,
-            display: [[Pixel::default(); 64]; 32],
+            vblank: true,
+            display: Display::default(),
+            quirks,
+            platform: Platform::Vip,
+            memory_map,
+            rpl: [0; 16],
+            halted: false,
+            planes: 1,
+            pattern: None,
+            pitch: 64,
+            redraw: false,
+            //seeded so runs are reproducible, use seed() to pick another sequence
+            rng: ChaCha8Rng::seed_from_u64(0),
         }
     }
 
-    pub fn load(mut self, filename: &str) -> std::io::Result<Self> {
-        let program = std::fs::read(filename)?;
-        self.memory[0x200..(0x200 + program.len())].copy_from_slice(&program);
-        self.pc = 0x200;
+    /// Seeds the random number generator used by `Rand`, so that the same seed and input always give the same run
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self
+    }
+
+    /// Allows the instructions added by SUPER-CHIP and XO-CHIP. Only the original instruction set runs without this
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    /// Lays out memory differently, which has to be done before loading a ROM as it clears memory
+    pub fn memory_map(mut self, memory_map: MemoryMap) -> Result<Self, MemoryError> {
+        self.memory = memory_map.memory()?;
+        self.decoded.clear();
+        self.memory_map = memory_map;
+        Ok(self)
+    }
+
+    /// Loads a ROM at the memory map's load address, and starts running it from there
+    pub fn load(mut self, program: &[u8]) -> Result<Self, MemoryError> {
+        self.memory_map.load(&mut self.memory, program)?;
+        self.decoded.clear();
+        self.pc = self.memory_map.load;
         Ok(self)
     }
 
-    fn fetch(&mut self) -> u16 {
-        let instruction = u16::from_be_bytes([
-            self.memory[self.pc as usize],
-            self.memory[(self.pc + 1) as usize],
-        ]);
-        self.inc_pc();
-        instruction
+    //read only access to the VM's state, for the debugger
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
     }
 
-    fn execute(&mut self, instruction: Instruction, keys: &Keys) -> Option<Display> {
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn timers(&self) -> (u8, u8) {
+        (self.delay_timer, self.sound_timer)
+    }
+
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// The addresses the last instruction wrote to, even if it wrote the same values that were already there
+    pub fn written(&self) -> Option<Range<usize>> {
+        self.written.clone()
+    }
+
+    fn fetch(&mut self) -> Result<u16, ErrorKind> {
+        let instruction = self.read_opcode(self.pc).ok_or(ErrorKind::PcOutOfBounds)?;
+        self.inc_pc()?;
+        Ok(instruction)
+    }
+
+    //fetch the instruction at the pc, only decoding it if it isn't cached
+    fn fetch_decoded(&mut self) -> Result<Decoded, ErrorKind> {
+        let decoded = match self.decoded.get(self.pc) {
+            Some(decoded) => decoded,
+            None => {
+                let opcode = self.read_opcode(self.pc).ok_or(ErrorKind::PcOutOfBounds)?;
+                self.decoded.insert(self.pc, (opcode, decode(opcode)))
+            }
+        };
+        self.inc_pc()?;
+        Ok(decoded)
+    }
+
+    pub fn read_opcode(&self, addr: u16) -> Option<u16> {
+        let bytes = self.memory.get(addr as usize..addr as usize + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Runs an instruction that's already been decoded, which `step` does after fetching one
+    pub fn execute(
+        &mut self,
+        instruction: Instruction,
+        keys: &Keys,
+    ) -> Result<Option<Display>, ErrorKind> {
         log::debug!("Executing instruction {instruction:?}");
+        self.written = None;
+        if instruction.platform() > self.platform {
+            return Err(ErrorKind::InvalidOpcode);
+        }
         match instruction {
             Instruction::Nop => (),
             Instruction::Cls => {
-                self.display = [[Black; 64]; 32];
-                return Some(self.display);
+                for plane in self.selected_planes() {
+                    self.display.clear_plane(plane);
+                }
+                return Ok(Some(self.display));
+            }
+            Instruction::Scd(n) => {
+                self.scroll_down(n as usize);
+                return Ok(Some(self.display));
+            }
+            Instruction::Scu(n) => {
+                self.scroll_up(n as usize);
+                return Ok(Some(self.display));
+            }
+            Instruction::Scr => {
+                self.scroll_right(4);
+                return Ok(Some(self.display));
+            }
+            Instruction::Scl => {
+                self.scroll_left(4);
+                return Ok(Some(self.display));
+            }
+            Instruction::Exit => self.halted = true,
+            Instruction::Lores => {
+                self.display = Display::new(Resolution::Low);
+                return Ok(Some(self.display));
+            }
+            Instruction::Hires => {
+                self.display = Display::new(Resolution::High);
+                return Ok(Some(self.display));
             }
             Instruction::Ret => {
-                self.pc = self.stack.pop().unwrap_or(0);
+                self.pc = self.stack.pop().ok_or(ErrorKind::StackUnderflow)?;
             }
             Instruction::Jmp(addr) => {
                 self.pc = addr;
             }
             Instruction::Call(addr) => {
+                if self.stack.len() == STACK_SIZE {
+                    return Err(ErrorKind::StackOverflow);
+                }
                 self.stack.push(self.pc);
                 self.pc = addr;
             }
@@ -118,51 +301,103 @@ impl VM {
                 self.index = nnn;
             }
             Instruction::Draw(rx, ry, n) => {
-                let range = (self.index as usize)..((self.index + n as u16) as usize);
-                let sprite = &self.memory[range];
-                let x = self.registers[rx as usize] % 64;
-                let y = self.registers[ry as usize] % 32;
-                self.registers[0xf] = 0;
-                for (i, row) in sprite.iter().enumerate() {
-                    if y + i as u8 > 31 {
-                        break;
+                //the VIP waited for the vertical blank interrupt before drawing
+                //so re-run the instruction until we're at the start of a frame
+                if self.quirks.display_wait {
+                    if !self.vblank {
+                        self.pc -= 2;
+                        return Ok(None);
                     }
-                    for (j, sprite_px) in (0..8).zip(PixIterator::new(row)) {
-                        if x + j as u8 > 63 {
+                    self.vblank = false;
+                }
+                //n = 0 means a 16x16 sprite, which is 2 bytes per row, but the VIP drew nothing
+                let (row_bytes, rows) = match n {
+                    0 if self.platform >= Platform::Schip => (2, 16),
+                    _ => (1, n as usize),
+                };
+                let (width, height) = (self.display.width(), self.display.height());
+                let x = self.registers[rx as usize] as usize % width;
+                let y = self.registers[ry as usize] as usize % height;
+                self.registers[0xf] = 0;
+
+                //each selected plane gets its own sprite, one after the other in memory
+                let mut addr = self.index as usize;
+                for plane in self.selected_planes() {
+                    let sprite = memory_slice(&self.memory, addr, row_bytes * rows)?;
+                    addr += row_bytes * rows;
+                    for (i, row) in sprite.chunks(row_bytes).enumerate() {
+                        let py = y + i;
+                        if self.quirks.clip && py >= height {
                             break;
                         }
-                        let display_px = &mut self.display[(y as usize + i)][(x as usize + j)];
+                        for (j, sprite_px) in row.iter().flat_map(PixIterator::new).enumerate() {
+                            let px = x + j;
+                            if self.quirks.clip && px >= width {
+                                break;
+                            }
+                            let display_px =
+                                &mut self.display.row_mut(plane, py % height)[px % width];
 
-                        //set vf high on collide
-                        if (*display_px & sprite_px).into() {
-                            self.registers[0xf] = 1;
-                        }
+                            //set vf high on collide
+                            if (*display_px & sprite_px).into() {
+                                self.registers[0xf] = 1;
+                            }
 
-                        //xor onto display
-                        *display_px ^= sprite_px;
+                            //xor onto display
+                            *display_px ^= sprite_px;
+                        }
                     }
                 }
-                return Some(self.display);
+                return Ok(Some(self.display));
             }
             Instruction::Ske(x, byte) => {
                 if self.registers[x as usize] == byte {
-                    self.inc_pc();
+                    self.skip()?;
                 }
             }
             Instruction::Skne(x, byte) => {
                 if self.registers[x as usize] != byte {
-                    self.inc_pc();
+                    self.skip()?;
                 }
             }
             Instruction::Skre(x, y) => {
                 if self.registers[x as usize] == self.registers[y as usize] {
-                    self.inc_pc();
+                    self.skip()?;
+                }
+            }
+            Instruction::Savr(x, y) => {
+                let len = x.abs_diff(y) as usize + 1;
+                let dest = memory_slice_mut(
+                    &mut self.memory,
+                    &mut self.decoded,
+                    &mut self.written,
+                    self.index as usize,
+                    len,
+                )?;
+                for (byte, reg) in dest.iter_mut().zip(register_range(x, y)) {
+                    *byte = self.registers[reg];
+                }
+            }
+            Instruction::Loadr(x, y) => {
+                let len = x.abs_diff(y) as usize + 1;
+                let src = memory_slice(&self.memory, self.index as usize, len)?;
+                for (byte, reg) in src.iter().zip(register_range(x, y)) {
+                    self.registers[reg] = *byte;
                 }
             }
             Instruction::Move(x, y) => self.registers[x as usize] = self.registers[y as usize],
-            Instruction::Or(x, y) => self.registers[x as usize] |= self.registers[y as usize],
-            Instruction::And(x, y) => self.registers[x as usize] &= self.registers[y as usize],
-            Instruction::Xor(x, y) => self.registers[x as usize] ^= self.registers[y as usize],
+            Instruction::Or(x, y) => {
+                self.registers[x as usize] |= self.registers[y as usize];
+                self.reset_vf();
+            }
+            Instruction::And(x, y) => {
+                self.registers[x as usize] &= self.registers[y as usize];
+                self.reset_vf();
+            }
+            Instruction::Xor(x, y) => {
+                self.registers[x as usize] ^= self.registers[y as usize];
+                self.reset_vf();
+            }
             Instruction::Add(x, y) => {
                 let (result, overflow) =
                     self.registers[x as usize].overflowing_add(self.registers[y as usize]);
@@ -170,44 +405,61 @@ impl VM {
                 self.registers[0xf] = overflow.into();
             }
             Instruction::Sub(x, y) => {
-                let (result, overflow) =
+                //vF is set when there's no borrow
+                let (result, borrow) =
                     self.registers[x as usize].overflowing_sub(self.registers[y as usize]);
                 self.registers[x as usize] = result;
-                self.registers[0xf] = overflow.into();
+                self.registers[0xf] = (!borrow).into();
             }
-            Instruction::Shr(x, _) => {
-                //y is ignored
-                self.registers[0xf] = 1 & self.registers[x as usize];
-                self.registers[x as usize] >>= 1;
+            Instruction::Shr(x, y) => {
+                let val = self.shift_operand(x, y);
+                self.registers[x as usize] = val >> 1;
+                self.registers[0xf] = val & 1;
             }
             Instruction::Ssub(x, y) => {
-                let (result, overflow) =
+                let (result, borrow) =
                     self.registers[y as usize].overflowing_sub(self.registers[x as usize]);
                 self.registers[x as usize] = result;
-                self.registers[0xf] = overflow.into();
+                self.registers[0xf] = (!borrow).into();
             }
-            Instruction::Shl(x, _) => {
-                //y is ignored
-                self.registers[0xf] = 0x80 & &self.registers[x as usize];
-                self.registers[x as usize] <<= 1;
+            Instruction::Shl(x, y) => {
+                let val = self.shift_operand(x, y);
+                self.registers[x as usize] = val << 1;
+                self.registers[0xf] = val >> 7;
             }
             Instruction::Skrne(x, r2) => {
                 if self.registers[x as usize] != self.registers[r2 as usize] {
-                    self.inc_pc();
+                    self.skip()?;
                 }
             }
-            Instruction::Jumpi(nnn) => self.pc = (nnn + self.registers[0] as u16) & 0xfff, //u12 wrap
-            Instruction::Rand(x, byte) => self.registers[x as usize] = random::<u8>() & byte,
+            Instruction::Jumpi(nnn) => {
+                //with the jump quirk, this is actually Bxnn and jumps to xnn + Vx
+                let r = if self.quirks.jump { nnn >> 8 } else { 0 };
+                self.pc = (nnn + self.registers[r as usize] as u16) & 0xfff; //u12 wrap
+            }
+            Instruction::Rand(x, byte) => self.registers[x as usize] = self.rng.gen::<u8>() & byte,
             Instruction::Skp(x) => {
-                if keys[self.registers[x as usize] as usize] {
-                    self.inc_pc()
+                //only the lowest nibble of Vx is used as the key
+                if keys[self.registers[x as usize] as usize & 0xf] {
+                    self.skip()?
                 }
             }
             Instruction::Sknp(x) => {
-                if !keys[self.registers[x as usize] as usize] {
-                    self.inc_pc()
+                if !keys[self.registers[x as usize] as usize & 0xf] {
+                    self.skip()?
                 }
             }
+            Instruction::Longi => {
+                //the address is the next word, so fetching it also moves the pc past it
+                self.index = self.fetch()?;
+            }
+            Instruction::Plane(n) => self.planes = n,
+            Instruction::Audio => {
+                let mut pattern = [0; 16];
+                pattern.copy_from_slice(memory_slice(&self.memory, self.index as usize, 16)?);
+                self.pattern = Some(pattern);
+            }
+            Instruction::Pitch(x) => self.pitch = self.registers[x as usize],
             Instruction::Moved(x) => self.registers[x as usize] = self.delay_timer,
             Instruction::Key(x) => {
                 //waiting is implemented by just re-running the instruction until a keypress is detected
@@ -229,16 +481,27 @@ impl VM {
             Instruction::Setrd(x) => self.delay_timer = self.registers[x as usize],
             Instruction::Setrs(x) => self.sound_timer = self.registers[x as usize],
             Instruction::Addi(x) => {
-                //weird wrapping arithmetic, u16+u8 but has to wrap to a u12
-                self.index = (self.index + (self.registers[x as usize] as u16)) & 0xfff;
+                //index is 16 bits wide to address all of XO-CHIP's memory
+                self.index = self.index.wrapping_add(self.registers[x as usize] as u16);
             }
             Instruction::Ldfnt(x) => {
-                //font starts at 0x50 in memory
-                let char_offset = self.registers[x as usize] as u16 * 5;
-                self.index = 0x50 + char_offset;
+                //only the low nibble picks a digit, like the original interpreters
+                self.index = self
+                    .memory_map
+                    .small_glyph(self.registers[x as usize] & 0xF);
+            }
+            Instruction::Ldbfnt(x) => {
+                //big font follows the small one
+                self.index = self.memory_map.big_glyph(self.registers[x as usize] & 0xF);
             }
             Instruction::Bcd(x) => {
-                let slice = &mut self.memory[(self.index as usize)..(self.index as usize + 3)];
+                let slice = memory_slice_mut(
+                    &mut self.memory,
+                    &mut self.decoded,
+                    &mut self.written,
+                    self.index as usize,
+                    3,
+                )?;
                 //binary encoded decimal conversion
                 let val = self.registers[x as usize];
                 slice[0] = val / 100;
@@ -246,24 +509,120 @@ impl VM {
                 slice[2] = val % 10;
             }
             Instruction::Store(x) => {
-                for reg in 0..=x as usize {
-                    self.memory[self.index as usize + reg] = self.registers[reg];
+                memory_slice_mut(
+                    &mut self.memory,
+                    &mut self.decoded,
+                    &mut self.written,
+                    self.index as usize,
+                    x as usize + 1,
+                )?
+                .copy_from_slice(&self.registers[..=x as usize]);
+                if self.quirks.memory_increment {
+                    self.index = self.index.wrapping_add(x as u16 + 1);
                 }
             }
             Instruction::Load(y) => {
-                for reg in 0..=y as usize {
-                    self.registers[reg] = self.memory[self.index as usize + reg];
+                self.registers[..=y as usize].copy_from_slice(memory_slice(
+                    &self.memory,
+                    self.index as usize,
+                    y as usize + 1,
+                )?);
+                if self.quirks.memory_increment {
+                    self.index = self.index.wrapping_add(y as u16 + 1);
                 }
             }
+            Instruction::Strpl(x) => {
+                self.rpl[..=x as usize].copy_from_slice(&self.registers[..=x as usize]);
+            }
+            Instruction::Ldrpl(x) => {
+                self.registers[..=x as usize].copy_from_slice(&self.rpl[..=x as usize]);
+            }
         };
-        None
+        Ok(None)
     }
 
     //helpers
-    //wrapping pc incremement so we dont forget to do it anywhere
-    fn inc_pc(&mut self) {
-        self.pc += 2;
-        self.pc &= 0xfff;
+    //checked pc incremement so we dont forget to do it anywhere
+    fn inc_pc(&mut self) -> Result<(), ErrorKind> {
+        self.pc = self.pc.checked_add(2).ok_or(ErrorKind::PcOutOfBounds)?;
+        Ok(())
+    }
+
+    //skip the next instruction, which might be XO-CHIP's double width F000 nnnn
+    fn skip(&mut self) -> Result<(), ErrorKind> {
+        if self.read_opcode(self.pc) == Some(0xF000) {
+            self.inc_pc()?;
+        }
+        self.inc_pc()
+    }
+
+    //the planes selected for drawing, as indexes into the display
+    fn selected_planes(&self) -> impl Iterator<Item = usize> {
+        let planes = self.planes;
+        (0..PLANES).filter(move |p| planes & (1 << p) != 0)
+    }
+
+    //the value shifted by 8xy6/8xyE, which depends on the shift quirk
+    fn shift_operand(&self, x: u8, y: u8) -> u8 {
+        if self.quirks.shift {
+            self.registers[x as usize]
+        } else {
+            self.registers[y as usize]
+        }
+    }
+
+    //scrolling moves the selected planes of the display, filling the gap left behind with black
+    fn scroll_down(&mut self, n: usize) {
+        for plane in self.selected_planes() {
+            for y in (0..self.display.height()).rev() {
+                let row = match y.checked_sub(n) {
+                    Some(src) => self.display.row(plane, src).to_vec(),
+                    None => vec![Pixel::Black; self.display.width()],
+                };
+                self.display.row_mut(plane, y).copy_from_slice(&row);
+            }
+        }
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        let height = self.display.height();
+        for plane in self.selected_planes() {
+            for y in 0..height {
+                let row = match y + n {
+                    src if src < height => self.display.row(plane, src).to_vec(),
+                    _ => vec![Pixel::Black; self.display.width()],
+                };
+                self.display.row_mut(plane, y).copy_from_slice(&row);
+            }
+        }
+    }
+
+    fn scroll_right(&mut self, n: usize) {
+        for plane in self.selected_planes() {
+            for y in 0..self.display.height() {
+                let row = self.display.row_mut(plane, y);
+                row.rotate_right(n);
+                row[..n].fill(Pixel::Black);
+            }
+        }
+    }
+
+    fn scroll_left(&mut self, n: usize) {
+        for plane in self.selected_planes() {
+            for y in 0..self.display.height() {
+                let row = self.display.row_mut(plane, y);
+                row.rotate_left(n);
+                let width = row.len();
+                row[width - n..].fill(Pixel::Black);
+            }
+        }
+    }
+
+    //the logical operations on the VIP clobbered vF as a side effect
+    fn reset_vf(&mut self) {
+        if self.quirks.vf_reset {
+            self.registers[0xf] = 0;
+        }
     }
 }
 //helpers here
@@ -287,6 +646,35 @@ fn eightbit(n: u16) -> u8 {
     (n & 0xff) as u8
 }
 
+//the slice of memory from start to start + len, or a memory fault at the first address outside of memory
+fn memory_slice(memory: &[u8], start: usize, len: usize) -> Result<&[u8], ErrorKind> {
+    memory
+        .get(start..start + len)
+        .ok_or(ErrorKind::MemoryFault(start.max(memory.len())))
+}
+
+//every write to memory goes through here, so it's where the instructions written over are forgotten,
+//and where the write is noted for watchpoints
+fn memory_slice_mut<'a>(
+    memory: &'a mut [u8],
+    decoded: &mut DecodeCache,
+    written: &mut Option<Range<usize>>,
+    start: usize,
+    len: usize,
+) -> Result<&'a mut [u8], ErrorKind> {
+    let fault = ErrorKind::MemoryFault(start.max(memory.len()));
+    let slice = memory.get_mut(start..start + len).ok_or(fault)?;
+    decoded.invalidate(start, len);
+    *written = Some(start..start + len);
+    Ok(slice)
+}
+
+//the registers from x to y inclusive, which counts down if y < x
+fn register_range(x: u8, y: u8) -> impl Iterator<Item = usize> {
+    let (x, y) = (x as usize, y as usize);
+    (0..=x.abs_diff(y)).map(move |i| if x <= y { x + i } else { x - i })
+}
+
 //helpers
 //an iterator over the bits of a byte, as pixels
 //this is totally unnecessary but I thought it was neat
@@ -0,0 +1,81 @@
+//CHIP-8 was never formally specified, so the interpreters written for different machines disagree on
+//what a handful of instructions do. ROMs tend to be written against one of them, so we have to pick.
+//see https://github.com/Timendus/chip8-test-suite#quirks-test for an overview
+
+/// The set of ambiguous behaviours the VM can be configured with.
+/// Each flag is `true` when the VM should behave in the "quirky" (non-VIP-like) way, except where noted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8xy6`/`8xyE` shift Vx in place and ignore Vy, rather than setting Vx to Vy shifted
+    pub shift: bool,
+    /// `Bnnn` jumps to nnn + Vx (where x is the top nibble of nnn), rather than nnn + V0
+    pub jump: bool,
+    /// `Fx55`/`Fx65` leave the index register pointing at the byte after the last register stored/loaded
+    pub memory_increment: bool,
+    /// `8xy1`/`8xy2`/`8xy3` reset vF to 0
+    pub vf_reset: bool,
+    /// `Dxyn` clips sprites at the edge of the screen, rather than wrapping them around to the other side
+    pub clip: bool,
+    /// `Dxyn` waits for the start of the next frame before drawing, limiting sprites to one per frame
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    /// The original COSMAC VIP interpreter
+    pub const VIP: Quirks = Quirks {
+        shift: false,
+        jump: false,
+        memory_increment: true,
+        vf_reset: true,
+        clip: true,
+        display_wait: true,
+    };
+
+    /// CHIP-48 and SUPER-CHIP on the HP48 calculators
+    pub const SCHIP: Quirks = Quirks {
+        shift: true,
+        jump: true,
+        memory_increment: false,
+        vf_reset: false,
+        clip: true,
+        display_wait: false,
+    };
+
+    /// XO-CHIP, as implemented by Octo
+    pub const XOCHIP: Quirks = Quirks {
+        shift: false,
+        jump: false,
+        memory_increment: true,
+        vf_reset: false,
+        clip: false,
+        display_wait: false,
+    };
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::VIP
+    }
+}
+
+/// The platforms we have quirks presets for, so they can be picked by name.
+/// They're in order, and each one has all the instructions of the ones before it
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Vip,
+    Schip,
+    Xochip,
+}
+
+impl Platform {
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Platform::Vip => Quirks::VIP,
+            Platform::Schip => Quirks::SCHIP,
+            Platform::Xochip => Quirks::XOCHIP,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//save states are a snapshot of the complete state of the VM, in a simple binary format
+//everything is big endian, and the format is versioned so old save states can be rejected cleanly
+//configuration such as the speed and quirks isn't included, as that comes from the command line
+
+use super::{PixIterator, MEMORY_SIZE, STACK_SIZE, VM};
+use chip8_base::{Display, Resolution, PLANES};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"C8SS";
+//version 2 added the random number generator's state
+//version 3 replaced the count of cycles into the frame with whether the frame has started, when timers started ticking separately
+const VERSION: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    NotASnapshot,
+    UnsupportedVersion(u8),
+    Truncated,
+    Corrupt,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::NotASnapshot => write!(f, "Not a CHIP-8 save state"),
+            SnapshotError::UnsupportedVersion(v) => write!(
+                f,
+                "Save state is version {v}, but only versions up to {VERSION} are supported"
+            ),
+            SnapshotError::Truncated => write!(f, "Save state ended unexpectedly"),
+            SnapshotError::Corrupt => write!(f, "Save state contains invalid data"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl VM {
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.memory.len() + 4096);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+
+        out.extend_from_slice(&(self.memory.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.memory);
+        out.extend_from_slice(&self.pc.to_be_bytes());
+        out.extend_from_slice(&self.index.to_be_bytes());
+        out.push(self.stack.len() as u8);
+        for addr in &self.stack {
+            out.extend_from_slice(&addr.to_be_bytes());
+        }
+        out.extend_from_slice(&self.registers);
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        out.push(self.vblank.into());
+        out.extend_from_slice(&self.rpl);
+        out.push(self.halted.into());
+        out.push(self.planes);
+        match self.pattern {
+            Some(pattern) => {
+                out.push(1);
+                out.extend_from_slice(&pattern);
+            }
+            None => out.push(0),
+        }
+        out.push(self.pitch);
+        out.extend_from_slice(&self.rng.get_seed());
+        out.extend_from_slice(&self.rng.get_word_pos().to_be_bytes());
+
+        //the display is packed 8 pixels to a byte, one plane after the other
+        out.push(match self.display.resolution() {
+            Resolution::Low => 0,
+            Resolution::High => 1,
+        });
+        for plane in 0..PLANES {
+            for y in 0..self.display.height() {
+                for byte in self.display.row(plane, y).chunks(8) {
+                    out.push(byte.iter().fold(0, |b, px| b << 1 | u8::from(*px)));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        let mut r = Reader(state);
+        if r.take(4).ok() != Some(&MAGIC[..]) {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = match r.u8()? {
+            v @ 1..=VERSION => v,
+            v => return Err(SnapshotError::UnsupportedVersion(v)),
+        };
+
+        //read everything into a new VM first, so a bad save state leaves us untouched
+        let mut vm = VM::new(1, self.quirks);
+        vm.speed = self.speed;
+        vm.platform = self.platform;
+        vm.memory_map = self.memory_map.clone();
+
+        if r.u32()? as usize != MEMORY_SIZE {
+            return Err(SnapshotError::Corrupt);
+        }
+        vm.memory = r.take(MEMORY_SIZE)?.to_vec();
+        vm.pc = r.u16()?;
+        vm.index = r.u16()?;
+        let depth = r.u8()?;
+        if depth as usize > STACK_SIZE {
+            return Err(SnapshotError::Corrupt);
+        }
+        vm.stack = (0..depth).map(|_| r.u16()).collect::<Result<_, _>>()?;
+        vm.registers.copy_from_slice(r.take(16)?);
+        vm.delay_timer = r.u8()?;
+        vm.sound_timer = r.u8()?;
+        vm.vblank = match version {
+            1 | 2 => r.u32()? == 0,
+            _ => r.u8()? != 0,
+        };
+        vm.rpl.copy_from_slice(r.take(16)?);
+        vm.halted = r.u8()? != 0;
+        vm.planes = r.u8()?;
+        vm.pattern = match r.u8()? {
+            0 => None,
+            _ => Some(r.take(16)?.try_into().unwrap()),
+        };
+        vm.pitch = r.u8()?;
+        //older save states keep the current generator
+        vm.rng = self.rng.clone();
+        if version >= 2 {
+            vm.rng = ChaCha8Rng::from_seed(r.take(32)?.try_into().unwrap());
+            vm.rng
+                .set_word_pos(u128::from_be_bytes(r.take(16)?.try_into().unwrap()));
+        }
+
+        vm.display = Display::new(match r.u8()? {
+            0 => Resolution::Low,
+            1 => Resolution::High,
+            _ => return Err(SnapshotError::Corrupt),
+        });
+        for plane in 0..PLANES {
+            for y in 0..vm.display.height() {
+                let bytes = r.take(vm.display.width() / 8)?;
+                let row = vm.display.row_mut(plane, y);
+                for (px, bit) in row.iter_mut().zip(bytes.iter().flat_map(PixIterator::new)) {
+                    *px = bit;
+                }
+            }
+        }
+        if !r.0.is_empty() {
+            return Err(SnapshotError::Corrupt);
+        }
+
+        *self = vm;
+        //make sure the restored display gets drawn
+        self.redraw = true;
+        Ok(())
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < n {
+            return Err(SnapshotError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
@@ -1,15 +1,606 @@
 #![cfg(test)]
 use super::*;
+use chip8_base::Pixel::*;
 use instruction::Instruction::*;
 
 // test that nop does nothing
 #[test]
 fn test_nop() {
-    let mut vm = VM::new(100);
-    vm.execute(Nop, &[false; 16]);
+    let mut vm = VM::new(100, Quirks::default());
+    vm.execute(Nop, &[false; 16]).unwrap();
 
-    let vm_2 = VM::new(100);
+    let vm_2 = VM::new(100, Quirks::default());
     assert_eq!(vm, vm_2);
 }
 
-//TODO: add more tests
+// test that the shift quirk picks which register is shifted
+#[test]
+fn test_shift_quirk() {
+    let mut vm = VM::new(100, Quirks::VIP);
+    vm.registers[1] = 0x81;
+    vm.execute(Shl(0, 1), &[false; 16]).unwrap();
+    assert_eq!(vm.registers[0], 0x02);
+    assert_eq!(vm.registers[0xf], 1);
+
+    let mut vm = VM::new(100, Quirks::SCHIP).platform(Platform::Schip);
+    vm.registers[0] = 0x03;
+    vm.registers[1] = 0x81;
+    vm.execute(Shr(0, 1), &[false; 16]).unwrap();
+    assert_eq!(vm.registers[0], 0x01);
+    assert_eq!(vm.registers[0xf], 1);
+}
+
+// test that subtracting sets vF when there's no borrow, and clears it when there is
+#[test]
+fn test_sub_flags() {
+    let mut vm = VM::new(100, Quirks::default());
+    vm.registers[..2].copy_from_slice(&[5, 3]);
+    vm.execute(Sub(0, 1), &[false; 16]).unwrap();
+    assert_eq!((vm.registers[0], vm.registers[0xF]), (2, 1));
+    vm.execute(Sub(0, 1), &[false; 16]).unwrap();
+    assert_eq!((vm.registers[0], vm.registers[0xF]), (0xFF, 0));
+
+    vm.registers[..2].copy_from_slice(&[3, 5]);
+    vm.execute(Ssub(0, 1), &[false; 16]).unwrap();
+    assert_eq!((vm.registers[0], vm.registers[0xF]), (2, 1));
+    vm.registers[..2].copy_from_slice(&[5, 3]);
+    vm.execute(Ssub(0, 1), &[false; 16]).unwrap();
+    assert_eq!((vm.registers[0], vm.registers[0xF]), (0xFE, 0));
+}
+
+// test that instructions from later platforms are only run when the VM is set up for them
+#[test]
+fn test_platform() {
+    let mut vm = VM::new(100, Quirks::VIP);
+    assert_eq!(
+        vm.execute(Hires, &[false; 16]),
+        Err(ErrorKind::InvalidOpcode)
+    );
+    assert_eq!(
+        vm.execute(Savr(0, 3), &[false; 16]),
+        Err(ErrorKind::InvalidOpcode)
+    );
+    vm.execute(Cls, &[false; 16]).unwrap();
+
+    let mut vm = vm.platform(Platform::Schip);
+    vm.execute(Hires, &[false; 16]).unwrap();
+    assert_eq!(
+        vm.execute(Longi, &[false; 16]),
+        Err(ErrorKind::InvalidOpcode)
+    );
+
+    let mut vm = vm.platform(Platform::Xochip);
+    vm.execute(Savr(0, 3), &[false; 16]).unwrap();
+}
+
+// test that the memory increment quirk moves the index register
+#[test]
+fn test_memory_increment_quirk() {
+    let mut vm = VM::new(100, Quirks::VIP);
+    vm.index = 0x300;
+    vm.execute(Store(3), &[false; 16]).unwrap();
+    assert_eq!(vm.index, 0x304);
+
+    let mut vm = VM::new(100, Quirks::SCHIP).platform(Platform::Schip);
+    vm.index = 0x300;
+    vm.execute(Load(3), &[false; 16]).unwrap();
+    assert_eq!(vm.index, 0x300);
+}
+
+// test that sprites drawn off the edge of the screen wrap or clip
+#[test]
+fn test_clip_quirk() {
+    let quirks = Quirks {
+        display_wait: false,
+        ..Quirks::VIP
+    };
+    let mut vm = VM::new(100, quirks);
+    vm.memory[0x300] = 0xFF;
+    vm.index = 0x300;
+    vm.registers[0] = 60;
+    vm.execute(Draw(0, 1, 1), &[false; 16]).unwrap();
+    assert_eq!(vm.display[0][63], White);
+    assert_eq!(vm.display[0][0], Black);
+
+    let mut vm = VM::new(100, Quirks::XOCHIP).platform(Platform::Xochip);
+    vm.memory[0x300] = 0xFF;
+    vm.index = 0x300;
+    vm.registers[0] = 60;
+    vm.execute(Draw(0, 1, 1), &[false; 16]).unwrap();
+    assert_eq!(vm.display[0][63], White);
+    assert_eq!(vm.display[0][3], White);
+}
+
+// test that a 16x16 sprite is drawn in high resolution mode
+#[test]
+fn test_hires_big_sprite() {
+    let mut vm = VM::new(100, Quirks::SCHIP).platform(Platform::Schip);
+    vm.execute(Hires, &[false; 16]).unwrap();
+    vm.memory[0x300..0x320].fill(0xFF);
+    vm.index = 0x300;
+    vm.registers[0] = 100;
+    vm.registers[1] = 40;
+    vm.execute(Draw(0, 1, 0), &[false; 16]).unwrap();
+    assert_eq!(vm.display.width(), 128);
+    assert_eq!(vm.display[40][100], White);
+    assert_eq!(vm.display[55][115], White);
+    assert_eq!(vm.display[56][115], Black);
+    assert_eq!(vm.display[40][116], Black);
+
+    //the VIP drew nothing for Dxy0
+    let mut vm = VM::new(100, Quirks::SCHIP);
+    vm.memory[0x300..0x320].fill(0xFF);
+    vm.index = 0x300;
+    vm.execute(Draw(0, 1, 0), &[false; 16]).unwrap();
+    assert_eq!(vm.display, Display::default());
+    assert_eq!(vm.registers[0xF], 0);
+}
+
+// test that scrolling moves pixels and fills the gap with black
+#[test]
+fn test_scroll() {
+    let mut vm = VM::new(100, Quirks::SCHIP).platform(Platform::Schip);
+    vm.display[0][0] = White;
+    vm.execute(Scd(2), &[false; 16]).unwrap();
+    assert_eq!(vm.display[0][0], Black);
+    assert_eq!(vm.display[2][0], White);
+    vm.execute(Scr, &[false; 16]).unwrap();
+    assert_eq!(vm.display[2][4], White);
+    vm.execute(Scl, &[false; 16]).unwrap();
+    vm.execute(Scl, &[false; 16]).unwrap();
+    assert_eq!(vm.display[2][0], Black);
+    assert_eq!(vm.display[2][60], Black);
+}
+
+// test that skipping jumps over the whole of a double width instruction
+#[test]
+fn test_skip_long_instruction() {
+    let mut vm = VM::new(100, Quirks::XOCHIP).platform(Platform::Xochip);
+    vm.memory[0x200..0x208].copy_from_slice(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0xF0, 0x00]);
+    vm.memory[0x208..0x20A].copy_from_slice(&[0xAB, 0xCD]);
+    vm.pc = 0x200;
+    vm.execute(Ske(0, 0), &[false; 16]).unwrap();
+    assert_eq!(vm.pc, 0x202);
+    vm.skip().unwrap();
+    assert_eq!(vm.pc, 0x206);
+    vm.fetch().unwrap();
+    vm.execute(Longi, &[false; 16]).unwrap();
+    assert_eq!(vm.index, 0xABCD);
+    assert_eq!(vm.pc, 0x20A);
+}
+
+// test that drawing to both planes reads a sprite for each and sets both colours
+#[test]
+fn test_draw_planes() {
+    let mut vm = VM::new(100, Quirks::XOCHIP).platform(Platform::Xochip);
+    vm.memory[0x300..0x302].copy_from_slice(&[0x80, 0xC0]);
+    vm.index = 0x300;
+    vm.execute(Plane(3), &[false; 16]).unwrap();
+    vm.execute(Draw(0, 0, 1), &[false; 16]).unwrap();
+    assert_eq!(vm.display.colour(0, 0), 3);
+    assert_eq!(vm.display.colour(1, 0), 2);
+    assert_eq!(vm.display.colour(2, 0), 0);
+
+    vm.execute(Plane(2), &[false; 16]).unwrap();
+    vm.execute(Cls, &[false; 16]).unwrap();
+    assert_eq!(vm.display.colour(0, 0), 1);
+}
+
+// test that register ranges can be saved and loaded in either order
+#[test]
+fn test_register_ranges() {
+    let mut vm = VM::new(100, Quirks::XOCHIP).platform(Platform::Xochip);
+    vm.registers[2..5].copy_from_slice(&[1, 2, 3]);
+    vm.index = 0x300;
+    vm.execute(Savr(4, 2), &[false; 16]).unwrap();
+    assert_eq!(vm.memory[0x300..0x303], [3, 2, 1]);
+    assert_eq!(vm.index, 0x300);
+    vm.execute(Loadr(5, 7), &[false; 16]).unwrap();
+    assert_eq!(vm.registers[5..8], [3, 2, 1]);
+}
+
+// test that invalid opcodes and stack problems are reported as errors with the pc and opcode
+#[test]
+fn test_errors() {
+    use chip8_base::Interpreter;
+
+    let mut vm = VM::new(100, Quirks::default());
+    vm.memory[0x200..0x204].copy_from_slice(&[0x00, 0xEE, 0xFF, 0xFF]);
+    vm.pc = 0x200;
+    let e = vm.step(&[false; 16]).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::StackUnderflow);
+    assert_eq!((e.pc, e.opcode), (0x200, Some(0x00EE)));
+
+    vm.pc = 0x202;
+    let e = vm.step(&[false; 16]).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::InvalidOpcode);
+    assert_eq!((e.pc, e.opcode), (0x202, Some(0xFFFF)));
+
+    vm.pc = 0xFFFF;
+    let e = vm.step(&[false; 16]).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::PcOutOfBounds);
+    assert_eq!(e.opcode, None);
+}
+
+// test that accessing memory past the end of the address space is a memory fault
+#[test]
+fn test_memory_fault() {
+    let mut vm = VM::new(100, Quirks::default());
+    vm.index = 0xFFFE;
+    assert_eq!(
+        vm.execute(Store(3), &[false; 16]),
+        Err(ErrorKind::MemoryFault(0x10000))
+    );
+    assert_eq!(
+        vm.execute(Bcd(0), &[false; 16]),
+        Err(ErrorKind::MemoryFault(0x10000))
+    );
+}
+
+// test that the IBM logo ROM draws the logo when run headlessly
+#[test]
+fn test_ibm_logo() {
+    let rom = concat!(env!("CARGO_MANIFEST_DIR"), "/../roms/IBM Logo.ch8");
+    let vm = VM::new(700, Quirks::default())
+        .load(&std::fs::read(rom).unwrap())
+        .unwrap();
+    let mut runner = chip8_base::Headless::new(vm);
+    runner.run_frames(60).unwrap();
+
+    let display = runner.display();
+    let lit = display.rows().flatten().filter(|px| **px == White).count();
+    assert_eq!(lit, 208);
+    assert_eq!(display[8][12], White);
+    assert_eq!(display[9][12], Black);
+    assert_eq!(runner.buzzer_history().len(), 60);
+    assert!(runner.buzzer_history().iter().all(|b| !b));
+}
+
+// test that a save state restores the VM exactly, and bad save states are rejected
+#[test]
+fn test_save_state() {
+    let rom = concat!(env!("CARGO_MANIFEST_DIR"), "/../roms/IBM Logo.ch8");
+    let vm = VM::new(700, Quirks::default())
+        .load(&std::fs::read(rom).unwrap())
+        .unwrap();
+    let mut runner = chip8_base::Headless::new(vm);
+    runner.run_frames(10).unwrap();
+    let mut vm = runner.into_inner().seed(7);
+    vm.stack.push(0x202);
+    vm.execute(Rand(0, 0xFF), &[false; 16]).unwrap();
+    let state = vm.save_state();
+
+    let mut restored = VM::new(700, Quirks::default());
+    restored.load_state(&state).unwrap();
+    restored.redraw = false;
+    assert_eq!(vm, restored);
+    vm.execute(Rand(0, 0xFF), &[false; 16]).unwrap();
+    restored.execute(Rand(0, 0xFF), &[false; 16]).unwrap();
+    assert_eq!(vm.registers[0], restored.registers[0]);
+
+    let mut version = state.clone();
+    version[4] = 99;
+    assert_eq!(
+        restored.load_state(&version),
+        Err(snapshot::SnapshotError::UnsupportedVersion(99))
+    );
+    assert_eq!(
+        restored.load_state(&state[..100]),
+        Err(snapshot::SnapshotError::Truncated)
+    );
+    assert_eq!(
+        restored.load_state(b"not a save state"),
+        Err(snapshot::SnapshotError::NotASnapshot)
+    );
+    //memory that isn't the right size, and anything after the display
+    let mut small = state[..5].to_vec();
+    small.extend_from_slice(&4096_u32.to_be_bytes());
+    small.extend_from_slice(&state[9..9 + 4096]);
+    small.extend_from_slice(&state[9 + MEMORY_SIZE..]);
+    assert_eq!(
+        restored.load_state(&small),
+        Err(snapshot::SnapshotError::Corrupt)
+    );
+    let mut trailing = state.clone();
+    trailing.push(0);
+    assert_eq!(
+        restored.load_state(&trailing),
+        Err(snapshot::SnapshotError::Corrupt)
+    );
+    assert_eq!(restored.memory.len(), MEMORY_SIZE);
+}
+
+// test that loading a save state keeps the memory map, so the font instructions still point at the fonts
+#[test]
+fn test_save_state_memory_map() {
+    let map = MemoryMap {
+        font: 0x100,
+        ..MemoryMap::STANDARD
+    };
+    let vm = VM::new(700, Quirks::default())
+        .memory_map(map.clone())
+        .unwrap()
+        .load(&[0x12, 0x00])
+        .unwrap();
+    let state = vm.save_state();
+
+    let mut restored = VM::new(700, Quirks::default()).memory_map(map).unwrap();
+    restored.load_state(&state).unwrap();
+    restored.registers[0] = 2;
+    restored.execute(Ldfnt(0), &[false; 16]).unwrap();
+    assert_eq!(restored.index, 0x10A);
+    assert_eq!(restored.memory[0x10A..0x10F], font::FONT[10..15]);
+}
+
+// test that encoding is the inverse of decoding, for every opcode except the unused 0nnn calls
+#[test]
+fn test_encode() {
+    for opcode in 0..=u16::MAX {
+        if let Some(instruction) = instruction::decode(opcode) {
+            if instruction != Nop || opcode == 0 {
+                assert_eq!(instruction.encode(), opcode, "{instruction}");
+            }
+        }
+    }
+}
+
+// test that the same seed always gives the same random numbers
+#[test]
+fn test_seed() {
+    let rolls = |seed| {
+        let mut vm = VM::new(700, Quirks::default()).seed(seed);
+        (0..8)
+            .map(|_| {
+                vm.execute(Rand(0, 0xFF), &[false; 16]).unwrap();
+                vm.registers[0]
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(rolls(1), rolls(1));
+    assert_ne!(rolls(1), rolls(2));
+}
+
+// test that replaying a movie of a run that uses random numbers gives exactly the same run
+#[test]
+fn test_movie() {
+    let rom = concat!(env!("CARGO_MANIFEST_DIR"), "/../roms/Pong.ch8");
+    let vm = VM::new(700, Quirks::default())
+        .seed(1234)
+        .load(&std::fs::read(rom).unwrap())
+        .unwrap();
+    let mut up = [false; 16];
+    up[1] = true;
+    let movie = chip8_base::Movie {
+        state: Some(vm.save_state()),
+        instructions_per_frame: Some(12),
+        events: vec![(100, up), (2000, [false; 16])],
+    };
+
+    let replay = || {
+        let vm = VM::new(700, Quirks::default()).seed(99);
+        let mut runner = chip8_base::Headless::new(vm).movie(&movie).unwrap();
+        runner.run_frames(300).unwrap();
+        runner.into_inner()
+    };
+    let (first, second) = (replay(), replay());
+    assert_eq!(first, second);
+
+    //the movie's seed is used, not the one the VM had
+    let vm = VM::new(700, Quirks::default())
+        .seed(1234)
+        .load(&std::fs::read(rom).unwrap())
+        .unwrap();
+    let mut runner = chip8_base::Headless::new(vm).script(movie.events.clone());
+    runner.run_frames(300).unwrap();
+    let mut direct = runner.into_inner();
+    direct.redraw = first.redraw;
+    assert_eq!(first, direct);
+}
+
+// test that the timers only count down on timer ticks, and display wait holds draws until one
+#[test]
+fn test_timers() {
+    use chip8_base::Interpreter;
+    let mut vm = VM::new(700, Quirks::VIP);
+    vm.memory[0x200..0x206].copy_from_slice(&[0xF0, 0x15, 0xD0, 0x01, 0xD0, 0x01]);
+    vm.pc = 0x200;
+    vm.registers[0] = 2;
+    let keys = [false; 16];
+
+    //setting the delay timer then drawing straight away, as the VM starts at the beginning of a frame
+    vm.step(&keys).unwrap();
+    vm.step(&keys).unwrap();
+    assert_eq!(vm.pc, 0x204);
+    //the second draw waits for the next frame, however many cycles run
+    assert!(vm.waiting_for_vblank());
+    for _ in 0..100 {
+        vm.step(&keys).unwrap();
+    }
+    assert_eq!(vm.pc, 0x204);
+    assert_eq!(vm.timers(), (2, 0));
+
+    vm.timer_tick();
+    assert_eq!(vm.timers(), (1, 0));
+    assert!(!vm.waiting_for_vblank());
+    vm.step(&keys).unwrap();
+    assert_eq!(vm.pc, 0x206);
+}
+
+// test that the memory map puts the fonts and ROM where it says, and rejects ROMs that don't fit
+#[test]
+fn test_memory_map() {
+    let vm = VM::new(700, Quirks::default()).load(&[0x12, 0x34]).unwrap();
+    assert_eq!(vm.memory[0x50..0x55], font::FONT[..5]);
+    assert_eq!(vm.memory[0xA0..0xAA], font::BIG_FONT[..10]);
+    assert_eq!(vm.memory[0x200..0x202], [0x12, 0x34]);
+    assert_eq!(vm.pc, 0x200);
+
+    let map = MemoryMap {
+        font: 0x100,
+        interpreter: Some(vec![0xAA; 0x200]),
+        load: 0x600,
+        ..MemoryMap::STANDARD
+    };
+    let mut vm = VM::new(700, Quirks::default())
+        .platform(Platform::Schip)
+        .memory_map(map)
+        .unwrap()
+        .load(&[0x12, 0x34])
+        .unwrap();
+    assert_eq!(vm.memory[0x00..0x100], [0xAA; 0x100]);
+    assert_eq!(vm.memory[0x100..0x150], font::FONT);
+    assert_eq!(vm.memory[0x150..0x1F0], font::BIG_FONT);
+    assert_eq!(vm.memory[0x1F0..0x200], [0xAA; 0x10]);
+    assert_eq!(vm.memory[0x600..0x602], [0x12, 0x34]);
+    assert_eq!(vm.pc, 0x600);
+    vm.registers[0] = 2;
+    vm.execute(Ldfnt(0), &[false; 16]).unwrap();
+    assert_eq!(vm.index, 0x10A);
+    vm.execute(Ldbfnt(0), &[false; 16]).unwrap();
+    assert_eq!(vm.index, 0x164);
+
+    //the largest ROM that fits, then one byte too many
+    let vm = || VM::new(700, Quirks::default());
+    assert!(vm().load(&vec![0; 0x10000 - 0x200]).is_ok());
+    assert_eq!(
+        vm().load(&vec![0; 0x10000 - 0x200 + 1]),
+        Err(MemoryError::TooLarge {
+            size: 0xFE01,
+            max: 0xFE00
+        })
+    );
+    let fonts_in_the_way = MemoryMap {
+        font: 0x300,
+        ..MemoryMap::STANDARD
+    };
+    assert_eq!(
+        vm().memory_map(fonts_in_the_way)
+            .and_then(|vm| vm.load(&[0; 0x101])),
+        Err(MemoryError::FontOverlap(0x300))
+    );
+    let big_image = MemoryMap {
+        interpreter: Some(vec![0; 0x201]),
+        ..MemoryMap::STANDARD
+    };
+    assert_eq!(
+        vm().memory_map(big_image).err(),
+        Some(MemoryError::ImageTooLarge {
+            size: 0x201,
+            max: 0x200
+        })
+    );
+    let off_the_end = MemoryMap {
+        font: 0xFFF0,
+        ..MemoryMap::STANDARD
+    };
+    assert_eq!(
+        vm().memory_map(off_the_end).err(),
+        Some(MemoryError::FontOutOfBounds(0xFFF0))
+    );
+}
+
+// test that fonts can be picked by name or loaded from files, and that Fx29 and Fx30 point into them
+#[test]
+fn test_fonts() {
+    assert_eq!(Font::named("octo"), Some(Font::default()));
+    assert_eq!(Font::named("DREAM-6800"), Font::named("dream6800"));
+    assert_eq!(Font::named("chip-9000"), None);
+    for (i, (name, font)) in Font::BUILT_IN.iter().enumerate() {
+        assert_eq!(Font::named(name).as_ref(), Some(font));
+        assert!(Font::BUILT_IN[..i].iter().all(|(_, f)| f != font), "{name}");
+    }
+
+    let vip = Font::named("vip").unwrap();
+    let map = MemoryMap {
+        glyphs: vip.clone(),
+        ..MemoryMap::STANDARD
+    };
+    let mut vm = VM::new(700, Quirks::default())
+        .platform(Platform::Schip)
+        .memory_map(map)
+        .unwrap();
+    vm.registers[0] = 1;
+    vm.execute(Ldfnt(0), &[false; 16]).unwrap();
+    assert_eq!(vm.memory[vm.index as usize..][..5], vip.small[5..10]);
+    vm.execute(Ldbfnt(0), &[false; 16]).unwrap();
+    assert_eq!(vm.memory[vm.index as usize..][..10], vip.big[10..20]);
+    //only the low nibble picks the digit
+    vm.registers[0] = 0x1A;
+    vm.execute(Ldfnt(0), &[false; 16]).unwrap();
+    assert_eq!(vm.index, 0x50 + 50);
+
+    let dir = std::env::temp_dir().join(format!("chip8-font-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let raw = dir.join("font.bin");
+    std::fs::write(&raw, vip.small).unwrap();
+    assert_eq!(
+        Font::load(&raw),
+        Ok(Font {
+            big: font::BIG_FONT,
+            ..vip.clone()
+        })
+    );
+    std::fs::write(&raw, [0; 100]).unwrap();
+    assert!(Font::load(&raw).is_err());
+
+    //both fonts drawn into a greyscale strip, which should read back the same
+    let schip = Font::named("schip").unwrap();
+    let mut pixels = vec![0x10; 128 * 15];
+    for y in 0..15 {
+        for g in 0..16 {
+            let byte = match y {
+                0..=4 => schip.small[g * 5 + y],
+                _ => schip.big[g * 10 + y - 5],
+            };
+            for x in 0..8 {
+                if byte << x & 0x80 != 0 {
+                    pixels[y * 128 + g * 8 + x] = 0xFF;
+                }
+            }
+        }
+    }
+    let image = dir.join("font.png");
+    let mut encoder = png::Encoder::new(std::fs::File::create(&image).unwrap(), 128, 15);
+    encoder.set_color(png::ColorType::Grayscale);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&pixels).unwrap();
+    writer.finish().unwrap();
+    assert_eq!(Font::load(&image), Ok(schip));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// test that writing over an instruction that has already run makes it run as what was written
+#[test]
+fn test_decode_cache() {
+    use chip8_base::Interpreter;
+    #[rustfmt::skip]
+    let program = [
+        0x61, 0x00, //v1 = 0, which the store rewrites
+        0x70, 0x01, //v0 += 1
+        0xA2, 0x01, //i = 0x201
+        0xF0, 0x55, //store v0 over the byte loaded into v1
+        0x12, 0x00, //jump back to the start
+    ];
+    let mut vm = VM::new(700, Quirks::default()).load(&program).unwrap();
+    let keys = [false; 16];
+    for n in 0..3 {
+        for _ in 0..5 {
+            vm.step(&keys).unwrap();
+        }
+        assert_eq!(vm.registers[1], n);
+        assert_eq!(vm.pc, 0x200);
+    }
+    vm.step(&keys).unwrap();
+    assert_eq!(vm.registers[1], 3);
+
+    //so does writing over the first byte of one, with bcd, which turns v0 = 0 into a nop
+    let mut vm = VM::new(700, Quirks::default())
+        .load(&[0x60, 0x00, 0xA2, 0x00, 0x60, 0x0D, 0xF0, 0x33, 0x12, 0x00])
+        .unwrap();
+    for _ in 0..5 {
+        vm.step(&keys).unwrap();
+    }
+    assert_eq!(vm.memory[0x200..0x203], [0, 1, 3]);
+    vm.step(&keys).unwrap();
+    assert_eq!(vm.registers[0], 13);
+}
@@ -0,0 +1,5 @@
+//! The CHIP-8 interpreter behind the `rs118-chip8` binary.
+//! It's a library too so the benchmarks and integration tests can get at the interpreter.
+
+pub mod conformance;
+pub mod interpreter;
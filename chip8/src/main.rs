@@ -1,24 +1,392 @@
-mod interpreter;
+mod asm;
+mod bench;
+mod config;
+mod debugger;
+mod disasm;
+mod romdb;
 
 use clap::Parser;
+use rs118_chip8::{conformance, interpreter};
+use std::path::PathBuf;
 
 fn main() {
     env_logger::init();
     let cli = Cli::parse();
 
-    let filename: &str = &cli.rom;
-    let cpu = interpreter::VM::new(700)
-        .load(filename)
-        .unwrap_or_else(|_| panic!("Could not load ROM: {}", filename));
-    chip8_base::run(cpu);
+    match cli.command {
+        Some(Command::Asm { source, output }) => {
+            let path = PathBuf::from(&source);
+            let text = std::fs::read_to_string(&path)
+                .unwrap_or_else(|_| panic!("Could not read source: {}", source));
+            let rom = asm::assemble(&path, &text).unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1)
+            });
+            let output = output.unwrap_or_else(|| path.with_extension("ch8"));
+            std::fs::write(&output, rom)
+                .unwrap_or_else(|_| panic!("Could not write to {}", output.display()));
+        }
+        Some(Command::Disasm { rom, output }) => {
+            let bytes =
+                std::fs::read(&rom).unwrap_or_else(|_| panic!("Could not load ROM: {}", rom));
+            let source = disasm::disassemble(&bytes).unwrap_or_else(|e| {
+                eprintln!("Could not disassemble {rom}: {e}");
+                std::process::exit(1)
+            });
+            match output {
+                Some(path) => std::fs::write(&path, source)
+                    .unwrap_or_else(|_| panic!("Could not write to {}", path.display())),
+                None => print!("{source}"),
+            }
+        }
+        Some(Command::Bench {
+            rom,
+            cycles,
+            quirks,
+            ipf,
+        }) => {
+            let bytes =
+                std::fs::read(&rom).unwrap_or_else(|_| panic!("Could not load ROM: {}", rom));
+            let known = romdb::lookup(&bytes);
+            let vm = interpreter::VM::new(700, quirks_for(quirks, known))
+                .platform(platform_for(quirks, known))
+                .load(&bytes)
+                .unwrap_or_else(|e| {
+                    eprintln!("Could not load ROM {rom}: {e}");
+                    std::process::exit(1)
+                });
+            let report = bench::run(vm, cycles, ipf.or(known.and_then(|rom| rom.tickrate)));
+            print!("{report}");
+        }
+        Some(Command::Test { roms, bless }) => {
+            let mut failed = false;
+            for case in &conformance::SUITE {
+                if bless && case.known_failure.is_some() {
+                    println!("{:<32} skipped, it's a known failure", case.rom);
+                } else if bless {
+                    match case.bless(&roms) {
+                        Ok(path) => println!("{:<32} saved {}", case.rom, path.display()),
+                        Err(e) => {
+                            println!("{:<32} {e}", case.rom);
+                            failed = true;
+                        }
+                    }
+                } else {
+                    let outcome = case.check(&roms);
+                    println!("{:<32} {outcome}", case.rom);
+                    failed |= !outcome.expected();
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        None => {
+            let filename: &str = &cli.rom.expect("a ROM is required without a subcommand");
+            let config = config::Config::load(cli.config.as_deref()).unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1)
+            });
+            let seed = cli.seed.unwrap_or_else(rand::random);
+            log::info!("Random seed is {seed}");
+            let bytes = std::fs::read(filename)
+                .unwrap_or_else(|_| panic!("Could not load ROM: {}", filename));
+            //known ROMs get their settings from the database, unless they're given on the command line
+            let known = romdb::lookup(&bytes);
+            match known {
+                Some(rom) => log::info!(
+                    "Recognised {}{}",
+                    rom.title,
+                    rom.author
+                        .as_ref()
+                        .map(|a| format!(" by {a}"))
+                        .unwrap_or_default()
+                ),
+                None => log::info!("ROM {} is not in the database", romdb::sha1(&bytes)),
+            }
+            let quirks = quirks_for(cli.quirks, known);
+            let memory_map = interpreter::MemoryMap {
+                font: cli.font_address,
+                load: cli.load_address,
+                interpreter: cli.interpreter_image.map(|path| {
+                    std::fs::read(&path).unwrap_or_else(|_| {
+                        panic!("Could not load interpreter image: {}", path.display())
+                    })
+                }),
+                glyphs: cli.font,
+            };
+            let cpu = interpreter::VM::new(cli.speed.unwrap_or(700), quirks)
+                .seed(seed)
+                .platform(platform_for(cli.quirks, known))
+                .memory_map(memory_map)
+                .and_then(|vm| vm.load(&bytes))
+                .unwrap_or_else(|e| {
+                    eprintln!("Could not load ROM {filename}: {e}");
+                    std::process::exit(1)
+                });
+            let name = match known {
+                Some(rom) => rom.title.clone(),
+                None => std::path::Path::new(filename)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            };
+            let mut keymap = cli.keymap.or(config.keymap).unwrap_or_default();
+            if let Some(Err(e)) = known.map(|rom| rom.bind_keys(&mut keymap)) {
+                log::error!("Could not use the database's keymap: {e}");
+            }
+            //a speed from the command line outranks the database's instructions per frame
+            let ipf = cli.ipf.or(known
+                .and_then(|rom| rom.tickrate)
+                .filter(|_| cli.speed.is_none()));
+            let mut options = chip8_base::RunOptions::new()
+                .title(format!("CHIP-8 - {name}"))
+                .window_scale(cli.window_scale)
+                .fullscreen(cli.fullscreen)
+                .audio(!cli.mute)
+                .frame_batching(!cli.no_batch)
+                .uncapped(cli.uncapped)
+                .frontend(cli.frontend)
+                .rewind_depth(cli.rewind)
+                .save_states(format!("chip8-{}", romdb::sha1(&bytes)))
+                .capture_scale(cli.capture_scale)
+                .palette(
+                    cli.palette
+                        .or(known.and_then(|rom| rom.palette))
+                        .or(config.palette)
+                        .unwrap_or_default(),
+                )
+                .keymap(keymap);
+            if let Some(n) = ipf {
+                options = options.instructions_per_frame(n);
+            }
+            if let Some(path) = cli.screenshot {
+                options = options.exit_screenshot(path);
+            }
+            if let Some(path) = cli.record {
+                options = options.record(path);
+            }
+            if let Some(path) = cli.replay {
+                let movie = chip8_base::Movie::read(&path)
+                    .unwrap_or_else(|e| panic!("Could not load movie {}: {e}", path.display()));
+                options = options.replay(movie);
+            }
+            let result = if cli.debug {
+                chip8_base::run_with(debugger::Debugger::new(cpu), options)
+            } else {
+                chip8_base::run_with(cpu, options)
+            };
+            if let Err(e) = result {
+                log::error!("{e:#}");
+                std::process::exit(1);
+            }
+        }
+    }
 }
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// A CHIP-8 ROM to load into the interpreter
-    #[clap(validator = rom_exists)]
-    rom: String,
+    #[clap(validator = rom_exists, required = true)]
+    rom: Option<String>,
+
+    /// The platform whose quirks the interpreter should emulate.
+    /// Known ROMs use the platform they were written for, and anything else defaults to vip
+    #[clap(long, value_enum)]
+    quirks: Option<interpreter::Platform>,
+
+    /// Where to show the display: window, or tui to draw it in the terminal, which works over SSH.
+    /// Logging goes to stderr, so redirect it when using tui
+    #[clap(long, default_value = "window")]
+    frontend: chip8_base::Frontend,
+
+    /// The address to load the ROM at and start running from, 0x600 for ETI-660 ROMs
+    #[clap(long, default_value = "0x200", value_parser = address)]
+    load_address: u16,
+
+    /// The address to put the built in font at, which some ROMs expect somewhere other than 0x50
+    #[clap(long, default_value = "0x50", value_parser = address)]
+    font_address: u16,
+
+    /// The font that programs draw digits with: octo, vip, dream6800, eti660, fish or schip,
+    /// or a file of 80, 160 or 240 bytes of glyphs, or a PNG of them in a row
+    #[clap(long, default_value = "octo", value_parser = font)]
+    font: interpreter::Font,
+
+    /// A file to copy into the start of memory, for ROMs that use code or data from the original interpreter
+    #[clap(long)]
+    interpreter_image: Option<PathBuf>,
+
+    /// The starting size of the window, in screen pixels per CHIP-8 pixel
+    #[clap(long, default_value_t = 5)]
+    window_scale: u32,
+
+    /// Start in fullscreen
+    #[clap(long)]
+    fullscreen: bool,
+
+    /// Turn off sound
+    #[clap(long)]
+    mute: bool,
+
+    /// How many instructions to run a second, 700 by default. Page Up and Page Down double and halve this while running
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    speed: Option<u32>,
+
+    /// How many instructions to run in each 60Hz frame, which overrides --speed.
+    /// Known ROMs run at the rate the ROM database recommends, unless either is given, which is about 12 by default.
+    /// The timers always count down at 60Hz, so this only changes how fast the program runs
+    #[clap(long)]
+    ipf: Option<u32>,
+
+    /// Sleep after every instruction, instead of running a frame's worth of instructions at once and sleeping until the next
+    #[clap(long)]
+    no_batch: bool,
+
+    /// Run as fast as possible, to see how fast the interpreter can go. The timers still tick every --ipf instructions,
+    /// and the title shows how many instructions a second are running
+    #[clap(long)]
+    uncapped: bool,
+
+    /// How many frames of history to keep for rewinding with Backspace, 0 disables rewinding
+    #[clap(long, default_value_t = 600)]
+    rewind: usize,
+
+    /// The colours to draw the display in: monochrome, octo, lcd-green, amber,
+    /// or 2 or 4 comma separated hex colours for the background, foreground, and XO-CHIP planes
+    #[clap(long)]
+    palette: Option<chip8_base::Palette>,
+
+    /// Which keys press each CHIP-8 key: a layout (qwerty, azerty, qwertz, dvorak, colemak),
+    /// then comma separated bindings like 5=W/Up. Press F1 while running to see the keymap
+    #[clap(long)]
+    keymap: Option<chip8_base::Keymap>,
+
+    /// Read settings from this TOML file, instead of config.toml in the rs118-chip8 config directory
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Start paused in the debugger, which reads commands from stdin
+    #[clap(long)]
+    debug: bool,
+
+    /// Seed the random number generator, so the same seed and input always play out the same way
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Record the input to a movie file, which can be played back with --replay
+    #[clap(long)]
+    record: Option<PathBuf>,
+
+    /// Play back a movie recorded with --record, instead of taking input from the keyboard
+    #[clap(long)]
+    replay: Option<PathBuf>,
+
+    /// Save a PNG of the display to a file when the interpreter exits
+    #[clap(long)]
+    screenshot: Option<PathBuf>,
+
+    /// How many image pixels each high resolution pixel takes up in screenshots and recordings
+    #[clap(long, default_value_t = 4)]
+    capture_scale: usize,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Assemble source into a ROM, in the same syntax that disasm prints
+    Asm {
+        /// The source file to assemble
+        #[clap(validator = rom_exists)]
+        source: String,
+
+        /// Where to write the ROM, which defaults to the source with a .ch8 extension
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Disassemble a ROM into assembly source, with labels for jump and call targets
+    Disasm {
+        /// The ROM to disassemble
+        #[clap(validator = rom_exists)]
+        rom: String,
+
+        /// Write the assembly to a file instead of stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Run a ROM as fast as possible with no display, then print how many instructions a second it ran
+    /// and how often each instruction ran. No keys are pressed
+    Bench {
+        /// The ROM to run
+        #[clap(validator = rom_exists)]
+        rom: String,
+
+        /// How many instructions to run
+        #[clap(long, default_value_t = 10_000_000)]
+        cycles: u64,
+
+        /// The platform whose quirks to emulate, the same as when running normally
+        #[clap(long, value_enum)]
+        quirks: Option<interpreter::Platform>,
+
+        /// How many instructions to run before each tick of the timers.
+        /// Known ROMs use the rate the ROM database recommends, and anything else 12
+        #[clap(long)]
+        ipf: Option<u32>,
+    },
+
+    /// Run the test ROMs and check that they draw the same as their golden images, failing if any don't
+    Test {
+        /// Where the test ROMs are, with the golden images in a golden directory inside it
+        #[clap(long, default_value = "roms")]
+        roms: PathBuf,
+
+        /// Save what each ROM draws as its golden image instead of checking it. Check the images by eye afterwards
+        #[clap(long)]
+        bless: bool,
+    },
+}
+
+//quirks from the command line, or the database if the ROM is known
+fn quirks_for(
+    platform: Option<interpreter::Platform>,
+    known: Option<&romdb::Rom>,
+) -> interpreter::Quirks {
+    match (platform, known) {
+        (Some(platform), _) => platform.quirks(),
+        (None, Some(rom)) => rom.quirks(),
+        (None, None) => interpreter::Quirks::default(),
+    }
+}
+
+//the platform from the command line or the database, which decides which instructions are allowed
+fn platform_for(
+    platform: Option<interpreter::Platform>,
+    known: Option<&romdb::Rom>,
+) -> interpreter::Platform {
+    platform
+        .or(known.map(|rom| rom.platform))
+        .unwrap_or(interpreter::Platform::Vip)
+}
+
+//addresses are in hex with a 0x prefix, or decimal
+fn address(s: &str) -> Result<u16, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| format!("Invalid address {s}: {e}"))
+}
+
+//fonts are built in ones by name, or files
+fn font(s: &str) -> Result<interpreter::Font, String> {
+    interpreter::Font::named(s).map_or_else(|| interpreter::Font::load(std::path::Path::new(s)), Ok)
 }
 
 fn rom_exists(f: &str) -> Result<(), &'static str> {
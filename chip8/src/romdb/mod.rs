@@ -0,0 +1,115 @@
+//a database of known ROMs, so they run with the platform, speed and controls they were written for without any flags
+//ROMs are looked up by the SHA-1 of their contents, the same way as the CHIP-8 community database,
+//and the database itself is roms.toml, which is built into the binary
+//
+//    [[rom]]
+//    sha1 = "b232ef880bd6060fb45fa6effed7edf0ae95670e"
+//    title = "Pong"
+//    author = "Paul Vervalin"
+//    platform = "vip"
+//    tickrate = 9
+//    keymap = { 1 = "Up", 4 = "Down" }
+//
+//quirks that differ from the platform's can be given too, like `quirks = { shift = true }`, and a palette by name or colours.
+//everything but the hash, title and platform is optional, and anything given on the command line takes priority
+
+mod test;
+
+use crate::interpreter::{Platform, Quirks};
+use chip8_base::{Keymap, Palette};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+const DATABASE: &str = include_str!("roms.toml");
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rom {
+    pub sha1: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub platform: Platform,
+    //how many instructions a frame the ROM plays best at, like --ipf
+    pub tickrate: Option<u32>,
+    //quirks that differ from the platform's
+    #[serde(default)]
+    pub quirks: QuirkOverrides,
+    //keys for the ROM's controls, by CHIP-8 key, which are added to the keymap in use rather than replacing it
+    #[serde(default)]
+    pub keymap: BTreeMap<String, String>,
+    #[serde(default, deserialize_with = "crate::config::parse")]
+    pub palette: Option<Palette>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuirkOverrides {
+    shift: Option<bool>,
+    jump: Option<bool>,
+    memory_increment: Option<bool>,
+    vf_reset: Option<bool>,
+    clip: Option<bool>,
+    display_wait: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct Database {
+    rom: Vec<Rom>,
+}
+
+impl Rom {
+    pub fn quirks(&self) -> Quirks {
+        let platform = self.platform.quirks();
+        let q = &self.quirks;
+        Quirks {
+            shift: q.shift.unwrap_or(platform.shift),
+            jump: q.jump.unwrap_or(platform.jump),
+            memory_increment: q.memory_increment.unwrap_or(platform.memory_increment),
+            vf_reset: q.vf_reset.unwrap_or(platform.vf_reset),
+            clip: q.clip.unwrap_or(platform.clip),
+            display_wait: q.display_wait.unwrap_or(platform.display_wait),
+        }
+    }
+
+    //adds the keys for the ROM's controls to a keymap, leaving alone any that are already in use
+    pub fn bind_keys(&self, keymap: &mut Keymap) -> Result<(), String> {
+        for (chip8_key, names) in &self.keymap {
+            let chip8_key = u8::from_str_radix(chip8_key, 16)
+                .ok()
+                .filter(|k| *k < 16)
+                .ok_or_else(|| format!("Invalid CHIP-8 key: {chip8_key}"))?;
+            for name in names.split('/').map(str::trim) {
+                let bound =
+                    (0..16).any(|k| keymap.bindings(k).any(|n| n.eq_ignore_ascii_case(name)));
+                if !bound {
+                    keymap.bind(chip8_key, name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//lowercase hex, as the hashes are written in the database
+pub fn sha1(rom: &[u8]) -> String {
+    Sha1::digest(rom)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn database() -> &'static [Rom] {
+    static ROMS: OnceLock<Vec<Rom>> = OnceLock::new();
+    ROMS.get_or_init(|| {
+        toml::from_str::<Database>(DATABASE)
+            .expect("the built in ROM database is valid")
+            .rom
+    })
+}
+
+pub fn lookup(rom: &[u8]) -> Option<&'static Rom> {
+    let hash = sha1(rom);
+    database().iter().find(|r| r.sha1 == hash)
+}
@@ -0,0 +1,62 @@
+#![cfg(test)]
+
+use super::*;
+use std::collections::HashSet;
+
+#[test]
+fn test_database() {
+    //every entry parses, and every hash is unique
+    let hashes: HashSet<&str> = database().iter().map(|rom| rom.sha1.as_str()).collect();
+    assert_eq!(hashes.len(), database().len());
+    for rom in database() {
+        assert!(
+            rom.sha1.len() == 40 && rom.sha1.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+        );
+        rom.bind_keys(&mut Keymap::empty())
+            .unwrap_or_else(|e| panic!("{}: {e}", rom.title));
+    }
+
+    let pong = lookup(include_bytes!("../../../roms/Pong.ch8")).unwrap();
+    assert_eq!(pong.title, "Pong");
+    assert_eq!(pong.quirks(), Quirks::VIP);
+    assert_eq!(pong.tickrate, Some(9));
+    assert!(lookup(b"not a ROM").is_none());
+    assert_eq!(sha1(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+}
+
+#[test]
+fn test_entry() {
+    let Database { rom } = toml::from_str(
+        r#"
+        [[rom]]
+        sha1 = "0000000000000000000000000000000000000000"
+        title = "Test"
+        platform = "schip"
+        quirks = { shift = false, display_wait = true }
+        keymap = { 5 = "Up/W", a = "Space" }
+        palette = "amber"
+        "#,
+    )
+    .unwrap();
+    let rom = &rom[0];
+    assert_eq!(
+        rom.quirks(),
+        Quirks {
+            shift: false,
+            display_wait: true,
+            ..Quirks::SCHIP
+        }
+    );
+    assert_eq!(rom.palette, Some(Palette::AMBER));
+
+    //W is already bound to 5, so it's left where it is
+    let mut keymap = Keymap::default();
+    rom.bind_keys(&mut keymap).unwrap();
+    assert_eq!(keymap.bindings(0x5).collect::<Vec<_>>(), ["W", "Up"]);
+    assert_eq!(keymap.bindings(0xA).collect::<Vec<_>>(), ["Z", "Space"]);
+
+    assert!(
+        toml::from_str::<Database>("[[rom]]\nsha1 = \"\"\ntitle = \"\"\nplatform = \"nes\"")
+            .is_err()
+    );
+}
@@ -0,0 +1,17 @@
+//runs the conformance suite the same way `chip8 test` does, so anything that changes what a test ROM draws fails here
+//if the change is meant to, run `chip8 test --bless` from the workspace root and check the new golden images
+
+use rs118_chip8::conformance::SUITE;
+use std::path::Path;
+
+#[test]
+fn test_conformance() {
+    let roms = Path::new(env!("CARGO_MANIFEST_DIR")).join("../roms");
+    let failures: Vec<_> = SUITE
+        .iter()
+        .map(|case| (case.rom, case.check(&roms)))
+        .filter(|(_, outcome)| !outcome.expected())
+        .map(|(rom, outcome)| format!("{rom}: {outcome}"))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}